@@ -1,6 +1,6 @@
 use serde::de::{DeserializeSeed, SeqAccess};
 
-use crate::error::{Error, Result};
+use crate::error::{Error, ErrorKind, Result};
 
 pub struct CommaSeparated<'a, 'de: 'a> {
     de: &'a mut crate::Deserializer<'de>,
@@ -9,10 +9,7 @@ pub struct CommaSeparated<'a, 'de: 'a> {
 
 impl<'a, 'de> CommaSeparated<'a, 'de> {
     pub fn new(de: &'a mut crate::Deserializer<'de>) -> Self {
-        CommaSeparated {
-            de,
-            first: true,
-        }
+        CommaSeparated { de, first: true }
     }
 }
 
@@ -25,37 +22,27 @@ impl<'de, 'a> SeqAccess<'de> for CommaSeparated<'a, 'de> {
     where
         T: DeserializeSeed<'de>,
     {
-        loop {
-            if crate::WHITESPACE.contains(self.de.peek_char()) {
-                self.de.next_char()?;
-            } else {
-                break;
-            }
-        }
+        self.de.skip_whitespace()?;
         // Check if there are no more elements.
         if self.de.peek_char() == '}' {
             return Ok(None);
         }
-        loop {
-            if crate::WHITESPACE.contains(self.de.peek_char()) {
-                self.de.next_char()?;
-            } else {
-                break;
-            }
-        }
         // Comma is required before every element except the first.
-        if !self.first && self.de.next_char()? != ',' {
-            return Err(Error::ExpectedArrayComma);
-        }
-        self.first = false;
-        loop {
-            if crate::WHITESPACE.contains(self.de.peek_char()) {
-                self.de.next_char()?;
-            } else {
-                break;
+        if !self.first {
+            if self.de.peek_char() != ',' {
+                return Err(self.de.error(ErrorKind::ExpectedArrayComma));
             }
+            self.de.next_char()?;
         }
+        self.first = false;
+        self.de.skip_whitespace()?;
         // Deserialize an array element.
-        seed.deserialize(&mut *self.de).map(Some)
+        let start = self.de.offset();
+        match self.de.parse_eval()? {
+            Some(value) => seed.deserialize(value),
+            None => seed.deserialize(&mut *self.de),
+        }
+        .map(Some)
+        .map_err(|e| self.de.locate_at(e, start))
     }
 }
@@ -1,16 +1,211 @@
-use serde::de::{DeserializeSeed, MapAccess};
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{
+    self, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
 
-use crate::error::{Error, Result};
+use crate::error::{Error, ErrorKind, Result};
+
+/// The name `Value` passes to `deserialize_newtype_struct`, which turns on
+/// `surface_declarations` for everything below it.
+pub(crate) const VALUE_TOKEN: &str = "$serde_arma::Value";
+
+/// The enum name used to serialize `Value::External` and `Value::Delete`.
+pub(crate) const DECLARATION_TOKEN: &str = "$serde_arma::Declaration";
+
+/// The enum name used to serialize `Value::Append` and `Value::Remove`.
+pub(crate) const OPERATOR_TOKEN: &str = "$serde_arma::Operator";
+
+/// The variant name under which `Value` receives a number as the text it was
+/// written as, and the newtype struct name it serializes that text with.
+pub(crate) const NUMBER_TOKEN: &str = "$serde_arma::Number";
+
+/// The key under which the parent of a class is passed to the visitor, before
+/// any of the class's own entries. A struct can capture it with
+/// `#[serde(rename = "$parent")] parent: Option<String>`.
+pub const PARENT_KEY: &str = "$parent";
 
 pub struct ArmaClass<'a, 'de: 'a> {
     de: &'a mut crate::Deserializer<'de>,
+    parent: Option<&'de str>,
+    parent_next: bool,
+    // The name of the entry whose value is read next.
+    key: Option<&'de str>,
+    // The fields of the struct being read, empty for maps.
+    fields: &'static [&'static str],
+    // Whether the entries are inside braces, which have to be closed. The
+    // root of a config ends with the input.
+    braced: bool,
 }
 
 impl<'a, 'de> ArmaClass<'a, 'de> {
-    pub fn new(de: &'a mut crate::Deserializer<'de>) -> Self {
+    pub fn new(
+        de: &'a mut crate::Deserializer<'de>,
+        parent: Option<&'de str>,
+        fields: &'static [&'static str],
+        braced: bool,
+    ) -> Self {
         ArmaClass {
             de,
+            parent,
+            parent_next: false,
+            key: None,
+            fields,
+            braced,
+        }
+    }
+
+    // The key given to the visitor for the entry `name`. When case is ignored
+    // it is the field spelled like `name`, preferring an exact match.
+    fn field(&self, name: &'de str) -> &'de str {
+        if !self.de.ignore_case {
+            return name;
+        }
+        self.fields
+            .iter()
+            .find(|f| **f == name)
+            .or_else(|| self.fields.iter().find(|f| f.eq_ignore_ascii_case(name)))
+            .map_or(name, |f| *f)
+    }
+
+    fn parse_key<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        // Only structs with a `$parent` field and `Value` are given the
+        // parent, other maps would take it for an entry.
+        if self.parent.is_some() && !self.parent_next {
+            self.parent_next = true;
+            if self.de.surface_declarations || self.fields.contains(&PARENT_KEY) {
+                return seed
+                    .deserialize(BorrowedStrDeserializer::new(PARENT_KEY))
+                    .map(Some);
+            }
+            self.parent = None;
+        }
+
+        loop {
+            // Check if there are no more entries.
+            self.de.skip_whitespace()?;
+            if self.de.peek_char() == '}' {
+                if !self.braced {
+                    return Err(self.de.error(ErrorKind::TrailingCharacters));
+                }
+                self.de.next_char()?;
+                return Ok(None);
+            }
+
+            if self.de.input.is_empty() {
+                if self.braced {
+                    return Err(self.de.error(ErrorKind::ExpectedMapEnd));
+                }
+                return Ok(None);
+            }
+
+            if self.de.parse_exec()? {
+                continue;
+            }
+
+            if self.de.starts_with_keyword("delete") {
+                // `delete Name;`
+                self.de.input = &self.de.input["delete".len()..];
+                self.de.skip_whitespace()?;
+                let name = self.de.parse_class_name();
+                self.de.skip_whitespace()?;
+                if self.de.peek_char() != ';' {
+                    return Err(self.de.error(ErrorKind::ExpectedSemiColon));
+                }
+                self.de.next_char()?;
+                if self.de.surface_declarations {
+                    self.de.declaration = Some(Declaration::Delete);
+                    self.key = Some(name);
+                    return seed
+                        .deserialize(BorrowedStrDeserializer::new(self.field(name)))
+                        .map(Some);
+                }
+                continue;
+            }
+
+            if self.de.starts_with_keyword("class") {
+                // `class Name`, `class Name: Parent`, `class Name : Parent` or
+                // the external declaration `class Name;`
+                self.de.input = &self.de.input["class".len()..];
+                self.de.skip_whitespace()?;
+                let name = self.de.parse_class_name();
+                self.de.skip_whitespace()?;
+                if self.de.peek_char() == ';' {
+                    self.de.next_char()?;
+                    if self.de.surface_declarations {
+                        self.de.declaration = Some(Declaration::External);
+                        self.key = Some(name);
+                        return seed
+                            .deserialize(BorrowedStrDeserializer::new(self.field(name)))
+                            .map(Some);
+                    }
+                    continue;
+                }
+                if self.de.peek_char() == ':' {
+                    self.de.parent = Some(self.de.parse_parent()?);
+                    self.de.skip_whitespace()?;
+                }
+                if self.de.peek_char() != '{' {
+                    return Err(self.de.error(ErrorKind::ExpectedMap));
+                }
+                self.de.next_is_class = true;
+                self.key = Some(name);
+                return seed
+                    .deserialize(BorrowedStrDeserializer::new(self.field(name)))
+                    .map(Some);
+            }
+
+            break;
         }
+
+        // Deserialize a map key.
+        let start = self.de.offset();
+        let key = self.de.parse_property_name()?;
+        self.de.skip_whitespace()?;
+        self.key = Some(key);
+        seed.deserialize(BorrowedStrDeserializer::new(self.field(key)))
+            .map(Some)
+            .map_err(|e| self.de.locate_at(e, start))
+    }
+
+    fn parse_value<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        if let Some(parent) = self.parent.take() {
+            return seed.deserialize(ParentDeserializer(parent));
+        }
+
+        if let Some(declaration) = self.de.declaration.take() {
+            return seed.deserialize(DeclarationDeserializer(declaration));
+        }
+
+        self.de.skip_whitespace()?;
+        if !self.de.next_is_class {
+            if self.de.peek_char() != '=' {
+                return Err(self.de.error(ErrorKind::ExpectedEquals));
+            }
+            self.de.next_char()?;
+        }
+        self.de.skip_whitespace()?;
+        // Deserialize a map value.
+        let start = self.de.offset();
+        let value = match self.de.parse_eval()? {
+            Some(value) => seed.deserialize(value),
+            None => seed.deserialize(&mut *self.de),
+        }
+        .map_err(|e| self.de.locate_at(e, start))?;
+        self.de.operator = None;
+        self.de.skip_whitespace()?;
+
+        if self.de.peek_char() != ';' {
+            return Err(self.de.error(ErrorKind::ExpectedSemiColon));
+        }
+        self.de.next_char()?;
+
+        Ok(value)
     }
 }
 
@@ -23,86 +218,384 @@ impl<'de, 'a> MapAccess<'de> for ArmaClass<'a, 'de> {
     where
         K: DeserializeSeed<'de>,
     {
-        // Check if there are no more entries.
-        loop {
-            let peek = self.de.peek_char();
-            if crate::WHITESPACE.contains(peek) {
-                self.de.next_char()?;
-            } else {
-                break;
-            }
+        self.parse_key(seed).map_err(|e| e.or_path(&self.de.path))
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        // The parent is read before any entry and has no key of its own.
+        let key = if self.parent.is_some() {
+            None
+        } else {
+            self.key.take()
+        };
+        self.de.path.extend(key);
+        let value = self.parse_value(seed).map_err(|e| e.or_path(&self.de.path));
+        if key.is_some() {
+            self.de.path.pop();
         }
-        if self.de.peek_char() == '}' {
-            self.de.next_char()?;
-            return Ok(None);
+        value
+    }
+}
+
+// The name of the parent class, which can be captured as a `String` or an
+// `Option<String>`.
+struct ParentDeserializer<'de>(&'de str);
+
+impl<'de> de::Deserializer<'de> for ParentDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Entries that declare or remove a class without giving it a body.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Declaration {
+    /// `class Name;`
+    External,
+    /// `delete Name;`
+    Delete,
+}
+
+impl Declaration {
+    pub(crate) fn variant(self) -> &'static str {
+        match self {
+            Declaration::External => "External",
+            Declaration::Delete => "Delete",
         }
+    }
+}
 
-        if self.de.peek_char() == '?' {
-            return Ok(None);
+// Declarations are unit variants named `External` and `Delete`, and read as
+// `None` when the target is an `Option`.
+pub(crate) struct DeclarationDeserializer(pub(crate) Declaration);
+
+impl<'de> de::Deserializer<'de> for DeclarationDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(BorrowedStrDeserializer::new(self.0.variant()))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_none()
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Array assignments that modify the inherited array.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Operator {
+    /// `name[] += {...};`
+    Append,
+    /// `name[] -= {...};`
+    Remove,
+}
+
+impl Operator {
+    pub(crate) fn variant(self) -> &'static str {
+        match self {
+            Operator::Append => "Append",
+            Operator::Remove => "Remove",
+        }
+    }
+}
+
+// Presents `name[] += {...}` as the newtype variant `Append` holding the array.
+pub(crate) struct OperatorAccess<'a, 'de: 'a> {
+    de: &'a mut crate::Deserializer<'de>,
+    operator: Operator,
+}
+
+impl<'a, 'de> OperatorAccess<'a, 'de> {
+    pub(crate) fn new(de: &'a mut crate::Deserializer<'de>, operator: Operator) -> Self {
+        OperatorAccess { de, operator }
+    }
+}
+
+impl<'de, 'a> EnumAccess<'de> for OperatorAccess<'a, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(BorrowedStrDeserializer::<Error>::new(
+            self.operator.variant(),
+        ))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a> VariantAccess<'de> for OperatorAccess<'a, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Err(self.de.error(ErrorKind::ExpectedArray))
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(self.de.error(ErrorKind::ExpectedArray))
+    }
+}
+
+// The entry inside the class of an enum field, which names the variant:
+// `Variant = value;` for newtype variants, `Variant[] = {...};` for tuple
+// variants and `class Variant {...};` for struct variants and newtype variants
+// holding a struct.
+pub(crate) struct VariantClass<'a, 'de: 'a> {
+    de: &'a mut crate::Deserializer<'de>,
+    class: bool,
+    // Given to the class of a newtype or struct variant, dropped for a unit
+    // variant.
+    parent: Option<&'de str>,
+}
+
+impl<'a, 'de> VariantClass<'a, 'de> {
+    pub(crate) fn new(de: &'a mut crate::Deserializer<'de>) -> Self {
+        VariantClass {
+            de,
+            class: false,
+            parent: None,
         }
+    }
 
-        if self.de.input.starts_with("class ") {
-            self.de.input = &self.de.input["class ".len()..];
+    // The `=` of a property, the class body is left to the value.
+    fn start_value(&mut self) -> Result<()> {
+        self.de.skip_whitespace()?;
+        if self.class {
             self.de.next_is_class = true;
-            loop {
-                if crate::WHITESPACE.contains(self.de.peek_char()) {
-                    self.de.next_char()?;
-                } else {
-                    break;
-                }
-            }
+            return Ok(());
         }
+        if self.de.peek_char() != '=' {
+            return Err(self.de.error(ErrorKind::ExpectedEquals));
+        }
+        self.de.next_char()?;
+        self.de.skip_whitespace()
+    }
 
-        // Deserialize a map key.
-        self.de.next_is_key = true;
-        let key = seed.deserialize(&mut *self.de).map(Some);
-        loop {
-            if crate::WHITESPACE.contains(self.de.peek_char()) {
-                self.de.next_char()?;
-            } else {
-                break;
-            }
+    fn end_value(&mut self) -> Result<()> {
+        self.de.next_is_class = false;
+        self.de.operator = None;
+        self.de.skip_whitespace()?;
+        if self.de.peek_char() != ';' {
+            return Err(self.de.error(ErrorKind::ExpectedSemiColon));
         }
-        self.de.next_is_key = false;
-        key
+        self.de.next_char()?;
+        Ok(())
     }
+}
 
-    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+impl<'de, 'a> EnumAccess<'de> for VariantClass<'a, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self)>
     where
         V: DeserializeSeed<'de>,
     {
-        // clear white space
-        loop {
-            if crate::WHITESPACE.contains(self.de.peek_char()) {
-                self.de.next_char()?;
-            } else {
-                break;
+        let start = self.de.offset();
+        let name = if self.de.starts_with_keyword("class") {
+            self.de.input = &self.de.input["class".len()..];
+            self.de.skip_whitespace()?;
+            self.class = true;
+            let name = self.de.parse_class_name();
+            self.de.skip_whitespace()?;
+            if self.de.peek_char() == ':' {
+                self.parent = Some(self.de.parse_parent()?);
             }
+            name
+        } else {
+            self.de.parse_property_name()?
+        };
+        let variant = seed
+            .deserialize(BorrowedStrDeserializer::<Error>::new(name))
+            .map_err(|e| self.de.locate_at(e, start))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a> VariantAccess<'de> for VariantClass<'a, 'de> {
+    type Error = Error;
+
+    // `class Variant {};`
+    fn unit_variant(mut self) -> Result<()> {
+        if !self.class {
+            return Err(self.de.error(ErrorKind::ExpectedMap));
         }
-        if !self.de.next_is_class && self.de.next_char()? != '=' {
-            return Err(Error::ExpectedEquals);
+        self.start_value()?;
+        if self.de.peek_char() != '{' {
+            return Err(self.de.error(ErrorKind::ExpectedMap));
         }
-        loop {
-            if crate::WHITESPACE.contains(self.de.peek_char()) {
-                self.de.next_char()?;
-            } else {
-                break;
+        self.de.next_char()?;
+        self.de.skip_whitespace()?;
+        if self.de.peek_char() != '}' {
+            return Err(self.de.error(ErrorKind::ExpectedMapEnd));
+        }
+        self.de.next_char()?;
+        self.end_value()
+    }
+
+    fn newtype_variant_seed<T>(mut self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.start_value()?;
+        self.de.parent = self.parent.take();
+        let value = seed.deserialize(&mut *self.de)?;
+        self.end_value()?;
+        Ok(value)
+    }
+
+    fn tuple_variant<V>(mut self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.class {
+            return Err(self.de.error(ErrorKind::ExpectedArray));
+        }
+        self.start_value()?;
+        let value = de::Deserializer::deserialize_seq(&mut *self.de, visitor)?;
+        self.end_value()?;
+        Ok(value)
+    }
+
+    fn struct_variant<V>(mut self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if !self.class {
+            return Err(self.de.error(ErrorKind::ExpectedMap));
+        }
+        self.start_value()?;
+        self.de.parent = self.parent.take();
+        let value = de::Deserializer::deserialize_struct(&mut *self.de, "", fields, visitor)?;
+        self.end_value()?;
+        Ok(value)
+    }
+}
+
+// The entries of a class read as a sequence of enums, each picking its
+// variant by its name, like `class Marker {...}; class Trigger {...};`.
+pub(crate) struct VariantEntries<'a, 'de: 'a> {
+    de: &'a mut crate::Deserializer<'de>,
+    braced: bool,
+}
+
+impl<'a, 'de> VariantEntries<'a, 'de> {
+    pub(crate) fn new(de: &'a mut crate::Deserializer<'de>, braced: bool) -> Self {
+        VariantEntries { de, braced }
+    }
+}
+
+impl<'de, 'a> SeqAccess<'de> for VariantEntries<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.de.skip_whitespace()?;
+        if self.de.input.is_empty() {
+            if self.braced {
+                return Err(self.de.error(ErrorKind::ExpectedMapEnd));
             }
+            return Ok(None);
         }
-        // Deserialize a map value.
-        let value = seed.deserialize(&mut *self.de);
-        loop {
-            if crate::WHITESPACE.contains(self.de.peek_char()) {
-                self.de.next_char()?;
-            } else {
-                break;
+        if self.de.peek_char() == '}' {
+            if !self.braced {
+                return Err(self.de.error(ErrorKind::TrailingCharacters));
             }
+            return Ok(None);
         }
+        seed.deserialize(VariantEntry(&mut *self.de)).map(Some)
+    }
+}
 
-        if self.de.next_char()? != ';' {
-            return Err(Error::ExpectedSemiColon);
-        }
+// One entry of `VariantEntries`, which can only be read as an enum.
+struct VariantEntry<'a, 'de: 'a>(&'a mut crate::Deserializer<'de>);
 
-        value
+impl<'de, 'a> de::Deserializer<'de> for VariantEntry<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(self.0.error(ErrorKind::ExpectedEnum))
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(VariantClass::new(self.0))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
     }
 }
@@ -0,0 +1,214 @@
+use std::fmt::{self, Display};
+
+use crate::error::{Error, ErrorKind, Position};
+
+/// Renders an `Error` like a compiler does, with the offending line of the
+/// source, a caret under the column and a hint on how to fix it.
+///
+/// ```text
+/// error: expected `;` after the entry in `numbers`
+///  --> config.cpp:1:19
+///   |
+/// 1 | numbers[] = {1, 2}
+///   |                   ^ missing `;` after the array
+/// ```
+pub struct Diagnostic<'a> {
+    error: &'a Error,
+    source: &'a str,
+    file_name: Option<&'a str>,
+}
+
+impl<'a> Diagnostic<'a> {
+    /// `source` must be the text the error was returned for, or the file
+    /// it names after `Preprocessed::translate`.
+    pub fn new(error: &'a Error, source: &'a str) -> Self {
+        Diagnostic {
+            error,
+            source,
+            file_name: error.file(),
+        }
+    }
+
+    /// The name shown in front of the line and column, the file of the error
+    /// by default.
+    pub fn file_name(mut self, name: &'a str) -> Self {
+        self.file_name = Some(name);
+        self
+    }
+
+    // Where the caret goes. A missing `;` is reported where the next entry
+    // starts, which is often on the following line, so the caret is moved
+    // back to the end of the entry that lacks it. An error in the expansion
+    // of a macro is at the start of the invocation, so it goes to its end.
+    fn caret(&self) -> Option<Position> {
+        let position = self.error.position()?;
+        if !self.source.is_char_boundary(position.offset) {
+            return None;
+        }
+        match *self.error.kind() {
+            ErrorKind::ExpectedSemiColon | ErrorKind::UnexpectedEof => {
+                if let Some(name) = self.error.macros().first() {
+                    let rest = &self.source[position.offset..];
+                    if rest.starts_with(name.as_str()) {
+                        let end = position.offset + invocation_len(rest, name.len());
+                        return Some(Position::from_offset(self.source, end));
+                    }
+                }
+                let end = self.source[..position.offset].trim_end().len();
+                Some(Position::from_offset(self.source, end))
+            }
+            _ => Some(position),
+        }
+    }
+
+    fn hint(&self, caret: Option<Position>) -> Option<String> {
+        let hint = match *self.error.kind() {
+            ErrorKind::ExpectedSemiColon => {
+                let end = caret.map_or(0, |c| c.offset);
+                format!("missing `;` after the {}", entry_kind(&self.source[..end]))
+            }
+            ErrorKind::ExpectedEquals => "properties are written as `name = value;`".to_string(),
+            ErrorKind::ExpectedArrayComma => "array elements are separated by `,`".to_string(),
+            ErrorKind::ExpectedArrayEnd => "missing `}` at the end of the array".to_string(),
+            ErrorKind::ExpectedMapEnd => "missing `};` at the end of the class".to_string(),
+            ErrorKind::ExpectedParent => {
+                "name the class to inherit from, or remove the `:`".to_string()
+            }
+            ErrorKind::ExpectedArray => {
+                "array properties are written as `name[] = {...};`".to_string()
+            }
+            ErrorKind::UnquotedString => "put the value in quotes".to_string(),
+            ErrorKind::UnexpectedEof => "the file ends in the middle of an entry".to_string(),
+            ErrorKind::TrailingCharacters => "there may be one `};` too many".to_string(),
+            ErrorKind::UnresolvedParent(ref name) => format!(
+                "define `{}` before inheriting from it, or declare it with `class {};`",
+                name, name
+            ),
+            ErrorKind::UnterminatedConditional => {
+                "every `#ifdef` and `#ifndef` needs an `#endif`".to_string()
+            }
+            _ => return None,
+        };
+        Some(hint)
+    }
+}
+
+// The length of the invocation at the start of `text` of a macro whose name
+// is `name_len` long, with its arguments if it has any.
+fn invocation_len(text: &str, name_len: usize) -> usize {
+    let after = text[name_len..].trim_start();
+    if !after.starts_with('(') {
+        return name_len;
+    }
+    let start = text.len() - after.len();
+    let mut depth = 0;
+    let mut quoted = false;
+    for (i, c) in after.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => {
+                depth -= 1;
+                if depth == 0 {
+                    return start + i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    name_len
+}
+
+// What ends at the end of `before`, so a hint can name it.
+fn entry_kind(before: &str) -> &'static str {
+    if !before.ends_with('}') {
+        return "value";
+    }
+    let mut depth = 0;
+    for (i, c) in before.char_indices().rev() {
+        match c {
+            '}' => depth += 1,
+            '{' => {
+                depth -= 1;
+                if depth == 0 {
+                    return if before[..i].trim_end().ends_with('=') {
+                        "array"
+                    } else {
+                        "class"
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+    "value"
+}
+
+impl<'a> Display for Diagnostic<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "error: {}", self.error.kind())?;
+        if let Some(path) = self.error.path() {
+            write!(f, " in `{}`", path)?;
+        }
+        writeln!(f)?;
+
+        let caret = self.caret();
+        let hint = self.hint(caret);
+        let caret = match caret {
+            Some(caret) => caret,
+            None => {
+                if let Some(hint) = hint {
+                    writeln!(f, "  = hint: {}", hint)?;
+                }
+                return Ok(());
+            }
+        };
+
+        let line = self.source.lines().nth(caret.line - 1).unwrap_or("");
+        let number = caret.line.to_string();
+        let gutter = " ".repeat(number.len());
+        writeln!(
+            f,
+            "{}--> {}:{}:{}",
+            gutter,
+            self.file_name.unwrap_or("<input>"),
+            caret.line,
+            caret.column
+        )?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", number, line)?;
+        // Tabs are copied so the caret lines up however wide they are shown.
+        let padding: String = line
+            .chars()
+            .take(caret.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        write!(f, "{} | {}^", gutter, padding)?;
+        if let Some(hint) = hint {
+            write!(f, " {}", hint)?;
+        }
+        writeln!(f)?;
+        if !self.error.macros().is_empty() {
+            let macros: Vec<String> = self
+                .error
+                .macros()
+                .iter()
+                .map(|m| format!("`{}`", m))
+                .collect();
+            writeln!(
+                f,
+                "{} = note: in the expansion of {}",
+                gutter,
+                macros.join(" > ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Error {
+    /// A `Diagnostic` that shows this error in `source`.
+    pub fn diagnostic<'a>(&'a self, source: &'a str) -> Diagnostic<'a> {
+        Diagnostic::new(self, source)
+    }
+}
@@ -1,16 +1,39 @@
-use std;
 use std::fmt::{self, Display};
 
 use serde::{de, ser};
 
+use crate::source_map::Location;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
-// This is a bare-bones implementation. A real library would provide additional
-// information in its error type, for example the line and column at which the
-// error occurred, the byte offset into the input, or the current key being
-// processed.
+/// An error together with the place in the input where it occurred.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    kind: ErrorKind,
+    position: Option<Position>,
+    path: Option<String>,
+    // Boxed, as most errors have none and `Error` is returned everywhere.
+    origin: Option<Box<Origin>>,
+}
+
+// The file of a preprocessed config an error is in.
 #[derive(Clone, Debug, PartialEq)]
-pub enum Error {
+struct Origin {
+    file: String,
+    macros: Vec<String>,
+}
+
+/// A location in the input. Lines and columns start at 1, columns count
+/// characters rather than bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorKind {
     // One or more variants that can be created by data structures through the
     // `ser::Error` and `de::Error` traits. For example the Serialize impl for
     // Mutex<T> might return an error because the mutex is poisoned, or the
@@ -18,50 +41,243 @@ pub enum Error {
     // field is missing.
     Message(String),
 
-    Eof,
+    UnexpectedEof,
     Syntax,
     ExpectedSemiColon,
     ExpectedEquals,
     ExpectedArrayComma,
     ExpectedString,
+    UnquotedString,
     TrailingCharacters,
-    // may remove
-    ExpectedMapComma,
-    ExpectedMapColon,
     ExpectedMapEnd,
     ExpectedMap,
+    ExpectedParent,
     ExpectedEnum,
     ExpectedArray,
     ExpectedArrayEnd,
     ExpectedNull,
     ExpectedBoolean,
     ExpectedInteger,
+    InvalidNumber(String),
+    NumberOutOfRange(String),
+
+    Io(String),
+    KeyMustBeAString,
+    Unsupported(&'static str),
+    UnresolvedParent(String),
+
+    UnknownDirective(String),
+    InvalidDirective(&'static str),
+    IncludeNotFound(String),
+    RecursiveInclude(String),
+    UnterminatedConditional,
+    MacroArguments(String, usize),
+    Eval(String),
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, position: Option<Position>) -> Self {
+        Error {
+            kind,
+            position,
+            path: None,
+            origin: None,
+        }
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Where in the input the error occurred. Errors raised while serializing
+    /// or resolving inheritance have no position.
+    pub fn position(&self) -> Option<Position> {
+        self.position
+    }
+
+    /// The classes and the entry that were being read when the error
+    /// occurred, joined with dots like `Mission.Entities.Item12.side`.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// The file the position is in, for errors raised while preprocessing.
+    pub fn file(&self) -> Option<&str> {
+        self.origin.as_ref().map(|o| o.file.as_str())
+    }
+
+    /// The macros whose expansion the error is in, outermost first.
+    pub fn macros(&self) -> &[String] {
+        self.origin.as_ref().map_or(&[], |o| &o.macros)
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.position.map(|p| p.line)
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        self.position.map(|p| p.column)
+    }
+
+    // Errors created through `de::Error::custom` don't know where they
+    // happened, the deserializer fills it in on the way out.
+    pub(crate) fn or_position(mut self, position: Position) -> Self {
+        if self.position.is_none() {
+            self.position = Some(position);
+        }
+        self
+    }
+
+    // The innermost class that sees the error records its path, the classes
+    // around it leave it alone.
+    pub(crate) fn or_path(mut self, path: &[&str]) -> Self {
+        if self.path.is_none() && !path.is_empty() {
+            self.path = Some(path.join("."));
+        }
+        self
+    }
+
+    // The file whose text the position counts from, set by the innermost
+    // include.
+    pub(crate) fn or_file(mut self, file: &str) -> Self {
+        if self.origin.is_none() {
+            self.origin = Some(Box::new(Origin {
+                file: file.to_string(),
+                macros: Vec::new(),
+            }));
+        }
+        self
+    }
+
+    // Moves the error from the preprocessed text to where it came from.
+    pub(crate) fn relocate(mut self, location: Location<'_>) -> Self {
+        self.position = Some(location.position);
+        self.origin = Some(Box::new(Origin {
+            file: location.file.to_string(),
+            macros: location.macros.to_vec(),
+        }));
+        self
+    }
+}
+
+impl Position {
+    pub(crate) fn from_offset(input: &str, offset: usize) -> Self {
+        let consumed = &input[..offset];
+        let line_start = consumed.rfind('\n').map_or(0, |i| i + 1);
+        Position {
+            offset,
+            line: consumed.matches('\n').count() + 1,
+            column: consumed[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Error::new(kind, None)
+    }
 }
 
 impl ser::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
-        Error::Message(msg.to_string())
+        ErrorKind::Message(msg.to_string()).into()
     }
 }
 
 impl de::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
-        Error::Message(msg.to_string())
+        ErrorKind::Message(msg.to_string()).into()
     }
 }
 
-impl Display for Error {
+impl Display for ErrorKind {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str(std::error::Error::description(self))
+        match *self {
+            ErrorKind::Message(ref msg) => formatter.write_str(msg),
+            ErrorKind::UnexpectedEof => formatter.write_str("unexpected end of input"),
+            ErrorKind::Syntax => formatter.write_str("expected a value"),
+            ErrorKind::ExpectedSemiColon => formatter.write_str("expected `;` after the entry"),
+            ErrorKind::ExpectedEquals => {
+                formatter.write_str("expected `=` after the property name")
+            }
+            ErrorKind::ExpectedArrayComma => {
+                formatter.write_str("expected `,` between array elements")
+            }
+            ErrorKind::ExpectedString => formatter.write_str("expected a string"),
+            ErrorKind::UnquotedString => formatter.write_str("string values must be quoted"),
+            ErrorKind::TrailingCharacters => {
+                formatter.write_str("unexpected characters after the end of the config")
+            }
+            ErrorKind::ExpectedMapEnd => {
+                formatter.write_str("expected `}` at the end of the class")
+            }
+            ErrorKind::ExpectedMap => formatter.write_str("expected a class"),
+            ErrorKind::ExpectedParent => {
+                formatter.write_str("expected the name of the parent class after `:`")
+            }
+            ErrorKind::ExpectedEnum => formatter.write_str("expected a string or a class"),
+            ErrorKind::ExpectedArray => formatter.write_str("expected an array"),
+            ErrorKind::ExpectedArrayEnd => {
+                formatter.write_str("expected `}` at the end of the array")
+            }
+            ErrorKind::ExpectedNull => formatter.write_str("expected `null`"),
+            ErrorKind::ExpectedBoolean => formatter.write_str("expected `0` or `1`"),
+            ErrorKind::ExpectedInteger => formatter.write_str("expected an integer"),
+            ErrorKind::InvalidNumber(ref text) => write!(formatter, "invalid number `{}`", text),
+            ErrorKind::NumberOutOfRange(ref text) => {
+                write!(formatter, "number `{}` is out of range", text)
+            }
+            ErrorKind::Io(ref msg) => write!(formatter, "io error: {}", msg),
+            ErrorKind::KeyMustBeAString => {
+                formatter.write_str("class and property names must be strings")
+            }
+            ErrorKind::Unsupported(what) => {
+                write!(formatter, "{} can not be represented in a config", what)
+            }
+            ErrorKind::UnresolvedParent(ref name) => {
+                write!(formatter, "parent class `{}` is not defined", name)
+            }
+            ErrorKind::UnknownDirective(ref name) => {
+                write!(formatter, "unknown preprocessor directive `#{}`", name)
+            }
+            ErrorKind::InvalidDirective(name) => write!(formatter, "malformed `#{}`", name),
+            ErrorKind::IncludeNotFound(ref path) => {
+                write!(formatter, "included file `{}` was not found", path)
+            }
+            ErrorKind::RecursiveInclude(ref path) => {
+                write!(formatter, "`{}` includes itself", path)
+            }
+            ErrorKind::UnterminatedConditional => formatter.write_str("missing `#endif`"),
+            ErrorKind::MacroArguments(ref name, count) => {
+                let plural = if count == 1 { "" } else { "s" };
+                write!(
+                    formatter,
+                    "macro `{}` takes {} argument{}",
+                    name, count, plural
+                )
+            }
+            ErrorKind::Eval(ref msg) => write!(formatter, "could not evaluate: {}", msg),
+        }
     }
 }
 
-impl std::error::Error for Error {
-    fn description(&self) -> &str {
-        match *self {
-            Error::Message(ref msg) => msg,
-            Error::Eof => "unexpected end of input",
-            _ => "no error messages eh"
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&self.kind, formatter)?;
+        if let Some(ref path) = self.path {
+            write!(formatter, " in `{}`", path)?;
+        }
+        if let Some(p) = self.position {
+            write!(formatter, " at line {} column {}", p.line, p.column)?;
         }
+        if let Some(file) = self.file() {
+            write!(formatter, " of `{}`", file)?;
+        }
+        if let Some(name) = self.macros().first() {
+            write!(formatter, " in the expansion of `{}`", name)?;
+        }
+        Ok(())
     }
 }
+
+impl std::error::Error for Error {}
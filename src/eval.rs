@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+
+use serde::de::{self, Visitor};
+
+use crate::error::{Error, Result};
+
+/// A value computed by `__EVAL`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Scalar {
+    Number(f64),
+    Bool(bool),
+    Str(String),
+}
+
+/// The variables assigned by `__EXEC` and `__EVAL`, by lowercase name as
+/// SQF ignores case.
+pub(crate) type Variables = HashMap<String, Scalar>;
+
+type EvalResult<T> = std::result::Result<T, String>;
+
+// The length of the parenthesized group at the start of `text`, including
+// both parentheses.
+pub(crate) fn group_len(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Runs the statements in `code`, separated by `;`, and returns the value of
+// the last one. Assignments have no value.
+pub(crate) fn run(code: &str, variables: &mut Variables) -> EvalResult<Option<Scalar>> {
+    let tokens = tokenize(code)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        i: 0,
+        variables,
+    };
+    let mut last = None;
+    loop {
+        while parser.eat(&Token::Op(";")) {}
+        if parser.i == tokens.len() {
+            return Ok(last);
+        }
+        last = parser.statement()?;
+        if parser.i < tokens.len() && !parser.eat(&Token::Op(";")) {
+            return Err(format!("unexpected {}", tokens[parser.i]));
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    // Lowercase, like variables.
+    Word(String),
+    Op(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Token::Number(n) => write!(f, "`{}`", n),
+            Token::Str(ref s) => write!(f, "`\"{}\"`", s),
+            Token::Word(ref w) => write!(f, "`{}`", w),
+            Token::Op(op) => write!(f, "`{}`", op),
+        }
+    }
+}
+
+// Longest first, so `==` is not read as two `=`.
+const OPERATORS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "^", "(", ")", ";", "=", "<", ">",
+    "!",
+];
+
+fn tokenize(code: &str) -> EvalResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = code.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if c == '"' || c == '\'' {
+            // A quote is written twice inside a string.
+            let mut s = String::new();
+            let mut chars = rest.char_indices().skip(1).peekable();
+            let mut end = None;
+            while let Some((i, d)) = chars.next() {
+                if d == c {
+                    if chars.peek().is_some_and(|&(_, e)| e == c) {
+                        chars.next();
+                    } else {
+                        end = Some(i + 1);
+                        break;
+                    }
+                }
+                s.push(d);
+            }
+            tokens.push(Token::Str(s));
+            end.ok_or("unterminated string")?
+        } else if c.is_ascii_digit() || c == '.' || c == '$' {
+            let mut len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '$')
+                .unwrap_or(rest.len());
+            // The sign of an exponent, like `1e-5`, belongs to the number.
+            let decimal = c != '$' && !rest.starts_with("0x") && !rest.starts_with("0X");
+            if decimal && rest[..len].ends_with(['e', 'E']) && rest[len..].starts_with(['+', '-']) {
+                len += 1 + rest[len + 1..]
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len() - len - 1);
+            }
+            let text = &rest[..len];
+            let hex = text
+                .strip_prefix("0x")
+                .or_else(|| text.strip_prefix("0X"))
+                .or_else(|| text.strip_prefix('$'));
+            let n = match hex {
+                Some(digits) => u64::from_str_radix(digits, 16).map(|n| n as f64).ok(),
+                None => text.parse().ok(),
+            };
+            let n = n.ok_or_else(|| format!("invalid number `{}`", text))?;
+            tokens.push(Token::Number(n));
+            len
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push(Token::Word(rest[..len].to_lowercase()));
+            len
+        } else {
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| format!("unsupported `{}`", c))?;
+            tokens.push(Token::Op(op));
+            op.len()
+        };
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    i: usize,
+    variables: &'a mut Variables,
+}
+
+// Commands that take one value on their right.
+const UNARY: &[&str] = &["str", "floor", "ceil", "round", "abs", "sqrt", "not"];
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.i)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.i += 1;
+        }
+        found
+    }
+
+    // The operator or command next in the input if it is one of `ops`.
+    fn eat_any(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        let op = match self.peek()? {
+            Token::Op(op) => ops.iter().find(|o| *o == op),
+            Token::Word(word) => ops.iter().find(|o| *o == word),
+            _ => None,
+        }?;
+        self.i += 1;
+        Some(op)
+    }
+
+    // `name = value`, `private name = value` or a value.
+    fn statement(&mut self) -> EvalResult<Option<Scalar>> {
+        let private = self.peek() == Some(&Token::Word("private".to_string()));
+        let start = self.i;
+        if private {
+            self.i += 1;
+        }
+        if let (Some(Token::Word(name)), Some(Token::Op("="))) =
+            (self.peek(), self.tokens.get(self.i + 1))
+        {
+            self.i += 2;
+            let value = self.expression()?;
+            self.variables.insert(name.clone(), value);
+            return Ok(None);
+        }
+        self.i = start;
+        self.expression().map(Some)
+    }
+
+    fn expression(&mut self) -> EvalResult<Scalar> {
+        let mut left = self.and()?;
+        while self.eat_any(&["||", "or"]).is_some() {
+            let right = self.and()?;
+            left = Scalar::Bool(left.as_bool()? || right.as_bool()?);
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> EvalResult<Scalar> {
+        let mut left = self.comparison()?;
+        while self.eat_any(&["&&", "and"]).is_some() {
+            let right = self.comparison()?;
+            left = Scalar::Bool(left.as_bool()? && right.as_bool()?);
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> EvalResult<Scalar> {
+        let mut left = self.min_max()?;
+        while let Some(op) = self.eat_any(&["==", "!=", "<=", ">=", "<", ">"]) {
+            let right = self.min_max()?;
+            let result = match (op, &left, &right) {
+                ("==", Scalar::Str(a), Scalar::Str(b)) => a.to_lowercase() == b.to_lowercase(),
+                ("!=", Scalar::Str(a), Scalar::Str(b)) => a.to_lowercase() != b.to_lowercase(),
+                ("==", Scalar::Bool(_), _) | ("!=", Scalar::Bool(_), _) => {
+                    return Err("booleans can not be compared".to_string())
+                }
+                _ => {
+                    let (a, b) = (left.as_number()?, right.as_number()?);
+                    match op {
+                        "==" => a == b,
+                        "!=" => a != b,
+                        "<=" => a <= b,
+                        ">=" => a >= b,
+                        "<" => a < b,
+                        _ => a > b,
+                    }
+                }
+            };
+            left = Scalar::Bool(result);
+        }
+        Ok(left)
+    }
+
+    fn min_max(&mut self) -> EvalResult<Scalar> {
+        let mut left = self.sum()?;
+        while let Some(op) = self.eat_any(&["max", "min"]) {
+            let (a, b) = (left.as_number()?, self.sum()?.as_number()?);
+            left = Scalar::Number(if op == "max" { a.max(b) } else { a.min(b) });
+        }
+        Ok(left)
+    }
+
+    fn sum(&mut self) -> EvalResult<Scalar> {
+        let mut left = self.product()?;
+        while let Some(op) = self.eat_any(&["+", "-"]) {
+            let right = self.product()?;
+            left = match (op, left, right) {
+                ("+", Scalar::Str(a), Scalar::Str(b)) => Scalar::Str(a + &b),
+                (op, left, right) => {
+                    let (a, b) = (left.as_number()?, right.as_number()?);
+                    Scalar::Number(if op == "+" { a + b } else { a - b })
+                }
+            };
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> EvalResult<Scalar> {
+        let mut left = self.power()?;
+        while let Some(op) = self.eat_any(&["*", "/", "%", "mod"]) {
+            let (a, b) = (left.as_number()?, self.power()?.as_number()?);
+            if op != "*" && b == 0.0 {
+                return Err("division by zero".to_string());
+            }
+            left = Scalar::Number(match op {
+                "*" => a * b,
+                "/" => a / b,
+                _ => a % b,
+            });
+        }
+        Ok(left)
+    }
+
+    fn power(&mut self) -> EvalResult<Scalar> {
+        let mut left = self.unary()?;
+        while self.eat(&Token::Op("^")) {
+            let (a, b) = (left.as_number()?, self.unary()?.as_number()?);
+            left = Scalar::Number(a.powf(b));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> EvalResult<Scalar> {
+        if let Some(op) = self.eat_any(&["-", "+", "!"]) {
+            let value = self.unary()?;
+            return Ok(match op {
+                "-" => Scalar::Number(-value.as_number()?),
+                "+" => Scalar::Number(value.as_number()?),
+                _ => Scalar::Bool(!value.as_bool()?),
+            });
+        }
+        if let Some(command) = self.eat_any(UNARY) {
+            let value = self.unary()?;
+            if command == "str" {
+                return Ok(Scalar::Str(value.to_sqf_string()));
+            }
+            if command == "not" {
+                return Ok(Scalar::Bool(!value.as_bool()?));
+            }
+            let n = value.as_number()?;
+            return Ok(Scalar::Number(match command {
+                "floor" => n.floor(),
+                "ceil" => n.ceil(),
+                "round" => n.round(),
+                "abs" => n.abs(),
+                _ => n.sqrt(),
+            }));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> EvalResult<Scalar> {
+        let token = self.peek().ok_or("expected a value")?;
+        self.i += 1;
+        match *token {
+            Token::Number(n) => Ok(Scalar::Number(n)),
+            Token::Str(ref s) => Ok(Scalar::Str(s.clone())),
+            Token::Word(ref word) if word == "true" => Ok(Scalar::Bool(true)),
+            Token::Word(ref word) if word == "false" => Ok(Scalar::Bool(false)),
+            Token::Word(ref word) if word == "pi" => Ok(Scalar::Number(std::f64::consts::PI)),
+            Token::Word(ref word) => self
+                .variables
+                .get(word)
+                .cloned()
+                .ok_or_else(|| format!("`{}` is not a variable or a supported command", word)),
+            Token::Op("(") => {
+                let value = self.expression()?;
+                if !self.eat(&Token::Op(")")) {
+                    return Err("expected `)`".to_string());
+                }
+                Ok(value)
+            }
+            ref token => Err(format!("unexpected {}", token)),
+        }
+    }
+}
+
+impl Scalar {
+    fn as_number(&self) -> EvalResult<f64> {
+        match *self {
+            Scalar::Number(n) => Ok(n),
+            _ => Err(format!("expected a number, found {}", self.to_sqf_string())),
+        }
+    }
+
+    fn as_bool(&self) -> EvalResult<bool> {
+        match *self {
+            Scalar::Bool(b) => Ok(b),
+            _ => Err(format!(
+                "expected a boolean, found {}",
+                self.to_sqf_string()
+            )),
+        }
+    }
+
+    // What `str` gives.
+    fn to_sqf_string(&self) -> String {
+        match *self {
+            Scalar::Number(n) => n.to_string(),
+            Scalar::Bool(b) => b.to_string(),
+            Scalar::Str(ref s) => format!("\"{}\"", s.replace('"', "\"\"")),
+        }
+    }
+}
+
+// Whole numbers are visited as integers, so `__EVAL(60*5)` fills a `u32`.
+impl<'de> de::Deserializer<'de> for Scalar {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Scalar::Number(n) if n.fract() == 0.0 && n.abs() < 9007199254740992.0 => {
+                visitor.visit_i64(n as i64)
+            }
+            Scalar::Number(n) => visitor.visit_f64(n),
+            Scalar::Bool(b) => visitor.visit_bool(b),
+            Scalar::Str(s) => visitor.visit_string(s),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Scalar::Number(n) if n == 0.0 || n == 1.0 => visitor.visit_bool(n == 1.0),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
@@ -0,0 +1,105 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::preprocess::FileSystem;
+
+/// Reads files from disk for the `Preprocessor`, finding includes the way the
+/// game does on a case-insensitive file system.
+///
+/// An include is looked up next to the file that includes it first. Paths
+/// starting with a separator, and relative paths that don't exist, are
+/// virtual paths like `\x\cba\addons\main\script_macros_common.hpp`. They are
+/// looked up below the directory of the longest matching prefix. A P: drive
+/// is the empty prefix.
+///
+/// ```no_run
+/// use serde_arma::IncludeResolver;
+///
+/// let resolver = IncludeResolver::new()
+///     .prefix(r"\x\cba\addons", "/build/CBA_A3/addons")
+///     .prefix("", "/build/p");
+/// let config: serde_arma::Value =
+///     serde_arma::from_file(resolver, "addons/main/config.cpp").unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct IncludeResolver {
+    prefixes: Vec<(Vec<String>, PathBuf)>,
+}
+
+impl IncludeResolver {
+    pub fn new() -> Self {
+        IncludeResolver::default()
+    }
+
+    /// Maps the virtual path `prefix`, like `\x\cba\addons\main` or the
+    /// `$PBOPREFIX$` of an addon, to the directory `dir`.
+    pub fn prefix<P: Into<PathBuf>>(mut self, prefix: &str, dir: P) -> Self {
+        let prefix = components(prefix).map(str::to_lowercase).collect();
+        self.prefixes.push((prefix, dir.into()));
+        self
+    }
+
+    fn resolve_virtual(&self, path: &str) -> Option<PathBuf> {
+        let parts: Vec<&str> = components(path).collect();
+        let (prefix, dir) = self
+            .prefixes
+            .iter()
+            .filter(|(prefix, _)| {
+                prefix.len() <= parts.len()
+                    && prefix
+                        .iter()
+                        .zip(&parts)
+                        .all(|(p, c)| *p == c.to_lowercase())
+            })
+            .max_by_key(|(prefix, _)| prefix.len())?;
+        find(dir, &parts[prefix.len()..])
+    }
+}
+
+impl FileSystem for IncludeResolver {
+    fn resolve(&self, path: &str, from: &str) -> io::Result<String> {
+        let absolute = path.starts_with('\\') || path.starts_with('/');
+        let dir = Path::new(from).parent().unwrap_or_else(|| Path::new(""));
+        let parts: Vec<&str> = components(path).collect();
+        let relative = if absolute { None } else { find(dir, &parts) };
+        relative
+            .or_else(|| self.resolve_virtual(path))
+            .map(|p| p.to_string_lossy().into_owned())
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    fn read(&self, name: &str) -> io::Result<String> {
+        fs::read_to_string(name)
+    }
+}
+
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['\\', '/'])
+        .filter(|c| !c.is_empty() && *c != ".")
+}
+
+// The file `parts` leads to from `dir`. A name that doesn't exist as it is
+// written matches an entry that differs only in case.
+fn find(dir: &Path, parts: &[&str]) -> Option<PathBuf> {
+    let mut path = dir.to_path_buf();
+    for part in parts {
+        let exact = path.join(part);
+        if *part == ".." || exact.exists() {
+            path = exact;
+            continue;
+        }
+        let listed = if path.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            &path
+        };
+        let lower = part.to_lowercase();
+        let entry = fs::read_dir(listed)
+            .ok()?
+            .filter_map(|e| e.ok())
+            .find(|e| e.file_name().to_string_lossy().to_lowercase() == lower)?;
+        path.push(entry.file_name());
+    }
+    Some(path).filter(|p| p.is_file())
+}
@@ -1,51 +1,153 @@
 use serde::Deserialize;
-use serde::de::{self, Visitor};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
 
 #[macro_use]
 extern crate lazy_static;
 
-use std::ops::{AddAssign, MulAssign, Neg};
+use std::borrow::Cow;
 
 mod array;
 mod class;
+mod diagnostic;
 mod error;
+mod eval;
+mod include;
+mod number;
+mod preprocess;
+mod resolve;
+mod ser;
+mod source_map;
+mod syntax;
+mod value;
 
 use crate::array::CommaSeparated;
-use crate::class::ArmaClass;
-use crate::error::{Error, Result};
+pub use crate::class::PARENT_KEY;
+use crate::class::{
+    ArmaClass, Declaration, Operator, OperatorAccess, VariantClass, VariantEntries, VALUE_TOKEN,
+};
+pub use crate::diagnostic::Diagnostic;
+pub use crate::error::{Error, ErrorKind, Position, Result};
+pub use crate::include::IncludeResolver;
+use crate::eval::{Scalar, Variables};
+use crate::number::{Literal, RawNumber};
+pub use crate::preprocess::{FileSystem, Preprocessed, Preprocessor};
+pub use crate::ser::{
+    to_string, to_string_pretty, to_writer, to_writer_pretty, FloatFormat, PrettyConfig, Serializer,
+};
+pub use crate::source_map::{Location, SourceMap};
+pub use crate::syntax::{ClassNode, Document, Node, PropertyNode};
+pub use crate::value::{from_value, Class, Number, Value};
 
 lazy_static! {
     static ref WHITESPACE: String = String::from(" \r\n\t");
-    static ref DIGIT_END: String = String::from(";,} \r\n\t");
+    static ref DIGIT_END: String = String::from(";,} \r\n\t/");
+}
+
+/// How bare words that other formats treat as literals are read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LiteralMode {
+    /// Like the game: there are no `true`, `false` or `null` keywords, they
+    /// are read as strings. Booleans are written as `0` and `1`.
+    #[default]
+    Arma,
+    /// `true` and `false` are booleans and `null` is a unit or `None`, as
+    /// well as `0` and `1` being booleans.
+    Lenient,
 }
 
 pub struct Deserializer<'de> {
+    // The whole input, positions are counted from its start.
+    original: &'de str,
     input: &'de str,
     next_is_class: bool,
-    next_is_key: bool,
-    first_reader: bool,
+    parent: Option<&'de str>,
+    declaration: Option<Declaration>,
+    operator: Option<Operator>,
+    surface_declarations: bool,
+    // Numbers are passed to `Value` with the text they were written as.
+    raw_numbers: bool,
+    strict_strings: bool,
+    literals: LiteralMode,
+    ignore_case: bool,
+    // Assigned by `__EXEC` and `__EVAL`.
+    variables: Variables,
+    // Names of the classes and the entry being read, for error messages.
+    path: Vec<&'de str>,
+    root: bool,
 }
 
 impl<'de> Deserializer<'de> {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &'de str) -> Self {
         Deserializer {
+            original: input,
             input,
             next_is_class: false,
-            next_is_key: false,
-            first_reader: false,
+            parent: None,
+            declaration: None,
+            operator: None,
+            surface_declarations: false,
+            raw_numbers: false,
+            strict_strings: false,
+            literals: LiteralMode::Arma,
+            ignore_case: false,
+            variables: Variables::new(),
+            path: Vec::new(),
+            root: true,
         }
     }
-    pub fn from_reader<R: std::io::Read>(mut reader: R) -> Self {
-        let mut text = String::new();
-        reader.read_to_string(&mut text).unwrap();
-        let sstr: &'static str = Box::leak(text.into_boxed_str());
-        Deserializer {
-            input: sstr,
-            next_is_class: false,
-            next_is_key: false,
-            first_reader: true,
+    /// A deserializer over UTF-8 encoded bytes, for use with crates like
+    /// `serde_transcode` that drive the deserializer themselves. Call `end`
+    /// afterwards to make sure all of the input was read.
+    pub fn from_slice(input: &'de [u8]) -> Result<Self> {
+        std::str::from_utf8(input)
+            .map(Deserializer::from_str)
+            .map_err(|e| ErrorKind::Io(e.to_string()).into())
+    }
+
+    /// Checks that nothing but whitespace is left after the config.
+    pub fn end(&mut self) -> Result<()> {
+        self.skip_whitespace()?;
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error(ErrorKind::TrailingCharacters))
         }
     }
+
+    /// Reject string values that are not in quotes, like `scope = public;`.
+    /// By default they are read as strings reaching up to the next `;`, `,`,
+    /// `}` or line break, as older configs and expanded macros contain them.
+    pub fn strict_strings(mut self, strict: bool) -> Self {
+        self.strict_strings = strict;
+        self
+    }
+
+    /// Choose whether `true`, `false` and `null` are keywords. They are not
+    /// by default, see `LiteralMode`.
+    pub fn literals(mut self, mode: LiteralMode) -> Self {
+        self.literals = mode;
+        self
+    }
+
+    /// Match class and property names to the fields of a struct regardless
+    /// of case, as the game does, so `class addonsmetadata` fills a field
+    /// renamed to `AddonsMetaData`. Keys of maps are left as written.
+    pub fn case_insensitive(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Pass external class declarations (`class Name;`) and `delete Name;`
+    /// entries on to the visitor instead of skipping them. They are read as
+    /// `None` by an `Option` and as the unit variants `External` and `Delete`
+    /// by an enum. Arrays assigned with `+=` or `-=` are read as the newtype
+    /// variants `Append` and `Remove` by `deserialize_any`, and as plain
+    /// arrays otherwise. `Value` always sees them.
+    pub fn surface_declarations(mut self, surface: bool) -> Self {
+        self.surface_declarations = surface;
+        self
+    }
 }
 
 pub fn from_str<'a, T>(s: &'a str) -> Result<T>
@@ -53,190 +155,380 @@ where
     T: Deserialize<'a>
 {
     let mut deserializer = Deserializer::from_str(s);
-    let t = T::deserialize(&mut deserializer)?;
-    if deserializer.input.is_empty() {
-        Ok(t)
-    } else {
-        Err(Error::TrailingCharacters)
-    }
+    let t = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
+    deserializer.end()?;
+    Ok(t)
 }
 
-pub fn from_reader<'a, R>(reader: R) -> Deserializer<'a>
+pub fn from_slice<'a, T>(v: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let mut deserializer = Deserializer::from_slice(v)?;
+    let t = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
+    deserializer.end()?;
+    Ok(t)
+}
+
+/// Reads the whole config from `reader` before deserializing it. The text is
+/// dropped afterwards, so `T` can not borrow from it.
+pub fn from_reader<R, T>(mut reader: R) -> Result<T>
 where
     R: std::io::Read,
+    T: DeserializeOwned,
 {
-    Deserializer::from_reader(reader)
+    let mut text = String::new();
+    reader
+        .read_to_string(&mut text)
+        .map_err(|e| Error::from(ErrorKind::Io(e.to_string())))?;
+    from_str(&text)
+}
+
+/// Runs `file` through the `Preprocessor`, reading it and the files it
+/// includes from `fs`, and deserializes the result. Errors point into the
+/// file they were made in, see `Preprocessed::translate`.
+pub fn from_file<F, T>(fs: F, file: &str) -> Result<T>
+where
+    F: FileSystem,
+    T: DeserializeOwned,
+{
+    let preprocessed = Preprocessor::new(fs).process(file)?;
+    from_str(preprocessed.text()).map_err(|e| preprocessed.translate(e))
 }
 
 impl<'de> Deserializer<'de> {
+    /// The position the deserializer has reached in the input.
+    pub fn position(&self) -> Position {
+        Position::from_offset(self.original, self.offset())
+    }
+
+    fn offset(&self) -> usize {
+        self.original.len() - self.input.len()
+    }
+
+    // An error at the current position.
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error::new(kind, Some(self.position()))
+    }
+
+    // Gives an error raised by a visitor the position where the value it
+    // rejected starts, unless it already has one.
+    fn locate_at(&self, err: Error, offset: usize) -> Error {
+        err.or_position(Position::from_offset(self.original, offset))
+    }
+
+    fn locate(&self, err: Error) -> Error {
+        self.locate_at(err, self.offset())
+    }
+
     fn peek_char(&mut self) -> char {
         self.input.chars().next().unwrap_or('?')
     }
 
     fn next_char(&mut self) -> Result<char> {
-        let ch = self.peek_char();
-        self.input = &self.input[ch.len_utf8()..];
-        Ok(ch)
+        match self.input.chars().next() {
+            Some(ch) => {
+                self.input = &self.input[ch.len_utf8()..];
+                Ok(ch)
+            }
+            None => Err(self.error(ErrorKind::UnexpectedEof)),
+        }
     }
 
-    fn parse_unsigned<T>(&mut self) -> Result<T>
+    fn parse_number(&mut self) -> Result<Literal> {
+        let (literal, len) = number::lex(self.input, &DIGIT_END).map_err(|e| self.error(e))?;
+        self.input = &self.input[len..];
+        Ok(literal)
+    }
+
+    fn parse_float(&mut self) -> Result<f64> {
+        self.parse_number().map(Literal::as_f64)
+    }
+
+    // Integers are visited with the type they were written as, the visitor
+    // checks that they fit.
+    fn deserialize_number<V>(&mut self, visitor: V) -> Result<V::Value>
     where
-        T: AddAssign<T> + MulAssign<T> + std::str::FromStr + std::fmt::Debug,
-        <T as std::str::FromStr>::Err: std::fmt::Debug,
+        V: Visitor<'de>,
     {
-        let mut s = String::new();
-        loop {
-            if DIGIT_END.contains(self.peek_char()) {
-                return Ok(s.parse::<T>().unwrap());
-            } else {
-                s.push(self.next_char()?);
+        match self.parse_number()? {
+            Literal::Unsigned(n) => visitor.visit_u64(n),
+            Literal::Signed(n) => visitor.visit_i64(n),
+            Literal::Float(n) => visitor.visit_f64(n),
+        }
+    }
+
+    // Whether the input is `word` and it is read as a keyword.
+    fn is_keyword(&self, word: &str) -> bool {
+        self.literals == LiteralMode::Lenient && self.is_bare_word(word)
+    }
+
+    fn parse_keyword(&mut self, word: &str) -> bool {
+        let found = self.is_keyword(word);
+        if found {
+            self.input = &self.input[word.len()..];
+        }
+        found
+    }
+
+    // `0` or `1`, or `true` or `false` in lenient mode.
+    fn parse_bool(&mut self) -> Result<bool> {
+        if self.parse_keyword("true") {
+            return Ok(true);
+        }
+        if self.parse_keyword("false") {
+            return Ok(false);
+        }
+        match number::lex(self.input, &DIGIT_END) {
+            Ok((Literal::Unsigned(n @ 0..=1), len)) => {
+                self.input = &self.input[len..];
+                Ok(n == 1)
             }
+            _ => Err(self.error(ErrorKind::ExpectedBoolean)),
         }
     }
 
-    fn parse_signed<T>(&mut self) -> Result<T>
-    where
-        T: Neg<Output = T> + AddAssign<T> + MulAssign<T> + std::str::FromStr + std::fmt::Debug,
-        <T as std::str::FromStr>::Err: std::fmt::Debug,
-    {
-        let mut s = String::new();
+    // Comments count as whitespace.
+    fn skip_whitespace(&mut self) -> Result<()> {
         loop {
-            if DIGIT_END.contains(self.peek_char()) {
-                return Ok(s.parse::<T>().unwrap());
+            if WHITESPACE.contains(self.peek_char()) {
+                self.next_char()?;
+            } else if self.input.starts_with("//") {
+                let end = self.input.find('\n').unwrap_or(self.input.len());
+                self.input = &self.input[end..];
+            } else if self.input.starts_with("/*") {
+                match self.input[2..].find("*/") {
+                    Some(end) => self.input = &self.input[end + 4..],
+                    None => {
+                        self.input = &self.input[self.input.len()..];
+                        return Err(self.error(ErrorKind::UnexpectedEof));
+                    }
+                }
             } else {
-                s.push(self.next_char()?);
+                return Ok(());
             }
         }
     }
 
-    fn parse_float<T>(&mut self) -> Result<T>
-    where
-        T: Neg<Output = T> + AddAssign<T> + MulAssign<T> + std::str::FromStr + std::fmt::Debug,
-        <T as std::str::FromStr>::Err: std::fmt::Debug,
-    {
-        let mut s = String::new();
+    fn starts_with_keyword(&self, keyword: &str) -> bool {
+        self.input.starts_with(keyword)
+            && self.input[keyword.len()..]
+                .chars()
+                .next()
+                .is_some_and(|c| WHITESPACE.contains(c) || c == '/')
+    }
+
+    fn parse_class_name(&mut self) -> &'de str {
+        let end = self
+            .input
+            .find(|c| WHITESPACE.contains(c) || ":{;/".contains(c))
+            .unwrap_or(self.input.len());
+        let name = &self.input[..end];
+        self.input = &self.input[end..];
+        name
+    }
+
+    // The parent class after the `:` of a class header.
+    fn parse_parent(&mut self) -> Result<&'de str> {
+        self.next_char()?;
+        self.skip_whitespace()?;
+        let parent = self.parse_class_name();
+        if parent.is_empty() {
+            return Err(self.error(ErrorKind::ExpectedParent));
+        }
+        Ok(parent)
+    }
+
+    // Quoted strings are borrowed from the input unless they contain `""` or
+    // the `" \n "` line break, which have to be unescaped into a new string.
+    fn parse_string(&mut self) -> Result<Cow<'de, str>> {
+        if self.peek_char() != '"' {
+            return self.parse_bare_string().map(Cow::Borrowed);
+        }
+        self.next_char()?;
+        let mut owned: Option<String> = None;
         loop {
-            if DIGIT_END.contains(self.peek_char()) {
-                return Ok(s.parse::<T>().unwrap());
+            let end = match self.input.find('"') {
+                Some(end) => end,
+                None => {
+                    self.input = &self.input[self.input.len()..];
+                    return Err(self.error(ErrorKind::UnexpectedEof));
+                }
+            };
+            let chunk = &self.input[..end];
+            self.input = &self.input[end + 1..];
+            let escape = if self.peek_char() == '"' {
+                self.next_char()?;
+                '"'
+            } else if self.input.starts_with(" \\n \"") {
+                self.input = &self.input[" \\n \"".len()..];
+                '\n'
             } else {
-                s.push(self.next_char()?);
+                return Ok(match owned {
+                    Some(mut s) => {
+                        s.push_str(chunk);
+                        Cow::Owned(s)
+                    }
+                    None => Cow::Borrowed(chunk),
+                });
+            };
+            let s = owned.get_or_insert_with(String::new);
+            s.push_str(chunk);
+            s.push(escape);
+        }
+    }
+
+    // The length of the unquoted value at the start of the input, without
+    // trailing whitespace.
+    fn bare_len(&self) -> usize {
+        let mut chars = self.input.char_indices().peekable();
+        let mut end = self.input.len();
+        while let Some((i, c)) = chars.next() {
+            let comment = c == '/' && matches!(chars.peek(), Some((_, '/')) | Some((_, '*')));
+            if ";,}\r\n".contains(c) || comment {
+                end = i;
+                break;
             }
         }
+        self.input[..end].trim_end().len()
     }
 
-    fn parse_bool(&mut self) -> Result<bool> {
-        if self.input.starts_with("true") {
-            self.input = &self.input["true".len()..];
-            Ok(true)
-        } else if self.input.starts_with("false") {
-            self.input = &self.input["false".len()..];
-            Ok(false)
-        } else {
-            Err(Error::ExpectedBoolean)
-        }
-    }
-
-    fn parse_string(&mut self) -> Result<&'de str> {
-        if self.next_is_class {
-            let mut s = String::new();
-            let mut i = 0;
-            let mut stop = WHITESPACE.clone();
-            stop.push('{');
-            loop {
-                if self.input.chars().nth(i).unwrap() == ':' && self.input.chars().nth(i+1).unwrap() == ' ' {
-                    s.push(':');
-                    i += 2;
-                } else {
-                    if stop.contains(self.input.chars().nth(i).unwrap()) {
-                        let s = &self.input[..i].trim();
-                        self.input = &self.input[i..];
-                        return Ok(s);
-                    } else {
-                        s.push(self.input.chars().nth(i).unwrap());
-                        i += 1;
-                    }
-                }
+    // Whether the input is an unquoted value that is exactly `word`.
+    fn is_bare_word(&self, word: &str) -> bool {
+        self.input.starts_with(word) && self.bare_len() == word.len()
+    }
+
+    // `model = \A3\data\model.p3d;` or `scope = public;`
+    fn parse_bare_string(&mut self) -> Result<&'de str> {
+        let len = self.bare_len();
+        if self.peek_char() == '{' {
+            return Err(self.error(ErrorKind::ExpectedString));
+        }
+        if len == 0 {
+            return Err(self.error(ErrorKind::Syntax));
+        }
+        if self.strict_strings {
+            return Err(self.error(ErrorKind::UnquotedString));
+        }
+        let s = &self.input[..len];
+        self.input = &self.input[len..];
+        Ok(s)
+    }
+
+    // The code of `__EVAL(...)` or `__EXEC(...)` if the input starts with
+    // the call to `name`.
+    fn parse_call(&mut self, name: &str) -> Result<Option<&'de str>> {
+        if !self.input.starts_with(name) {
+            return Ok(None);
+        }
+        let args = self.input[name.len()..].trim_start();
+        if !args.starts_with('(') {
+            return Ok(None);
+        }
+        let len = match eval::group_len(args) {
+            Some(len) => len,
+            None => {
+                self.input = &self.input[self.input.len()..];
+                return Err(self.error(ErrorKind::UnexpectedEof));
             }
-        } else if self.peek_char() == '"' {
+        };
+        self.input = &args[len..];
+        Ok(Some(&args[1..len - 1]))
+    }
+
+    // `value = __EVAL(60 * 5);` computes the value while the config is read.
+    fn parse_eval(&mut self) -> Result<Option<Scalar>> {
+        let start = self.offset();
+        let code = match self.parse_call("__EVAL")? {
+            Some(code) => code,
+            None => return Ok(None),
+        };
+        match eval::run(code, &mut self.variables) {
+            Ok(Some(value)) => Ok(Some(value)),
+            Ok(None) => Err(self.locate_at(
+                ErrorKind::Eval("the code gives no value".to_string()).into(),
+                start,
+            )),
+            Err(msg) => Err(self.locate_at(ErrorKind::Eval(msg).into(), start)),
+        }
+    }
+
+    // `__EXEC(x = 5)` between the entries of a class, an optional `;` after
+    // it is skipped.
+    fn parse_exec(&mut self) -> Result<bool> {
+        let start = self.offset();
+        let code = match self.parse_call("__EXEC")? {
+            Some(code) => code,
+            None => return Ok(false),
+        };
+        eval::run(code, &mut self.variables)
+            .map_err(|msg| self.locate_at(ErrorKind::Eval(msg).into(), start))?;
+        self.skip_whitespace()?;
+        if self.peek_char() == ';' {
             self.next_char()?;
-            let mut s = String::new();
-            loop {
-                let c = self.next_char()?;
-                if c == '"' {
-                    if self.peek_char() == '"' {
-                        self.next_char()?;
-                        s.push('"');
-                    } else {
-                        if self.input.starts_with(" \\n \"") {
-                            self.input = &self.input[" \\n \"".len()..];
-                            s.push('\n');
-                        } else {
-                            break;
-                        }
-                    }
-                } else {
-                    s.push(c);
-                }
-            }
-            let sstr: &'static str = Box::leak(s.into_boxed_str());
-            Ok(sstr)
-        } else {
-            match self.input.find('=') {
-                Some(len) => {
-                    let s = &self.input[..len].trim();
-                    self.input = &self.input[len..];
-                    if let Some(pos) = s.find('[') {
-                        return Ok(&s[..pos]);
-                    }
-                    Ok(s)
-                }
-                None => {
-                    println!("eof: |{}|", self.input);
-                    Err(Error::Eof)
-                },
+        }
+        Ok(true)
+    }
+
+    // The name on the left of `=`, without the `[]` of arrays. Stops in
+    // front of the `=`.
+    fn parse_property_name(&mut self) -> Result<&'de str> {
+        let end = self
+            .input
+            .find(|c| WHITESPACE.contains(c) || "[]=+-/;{},".contains(c))
+            .unwrap_or(self.input.len());
+        if end == 0 {
+            return Err(self.error(ErrorKind::Syntax));
+        }
+        let name = &self.input[..end];
+        self.input = &self.input[end..];
+        self.skip_whitespace()?;
+        if self.peek_char() == '[' {
+            self.next_char()?;
+            self.skip_whitespace()?;
+            if self.peek_char() != ']' {
+                return Err(self.error(ErrorKind::ExpectedArrayEnd));
             }
+            self.next_char()?;
+            self.skip_whitespace()?;
+        }
+        // `name[] += {...};` and `name[] -= {...};`
+        if self.input.starts_with("+=") {
+            self.operator = Some(Operator::Append);
+            self.next_char()?;
+        } else if self.input.starts_with("-=") {
+            self.operator = Some(Operator::Remove);
+            self.next_char()?;
         }
+        Ok(name)
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        if self.first_reader {
-            self.first_reader = false;
+        if self.root {
             self.deserialize_struct("", &[], visitor)
-        } else if self.next_is_key {
-            self.next_is_key = false;
-            self.deserialize_str(visitor)
+        } else if let Some(operator) = self.operator.take().filter(|_| self.surface_declarations) {
+            visitor.visit_enum(OperatorAccess::new(self, operator))
         } else {
             match self.peek_char() {
-                'n' => self.deserialize_unit(visitor),
-                't' | 'f' => self.deserialize_bool(visitor),
+                'n' if self.is_keyword("null") => self.deserialize_unit(visitor),
+                't' if self.is_keyword("true") => self.deserialize_bool(visitor),
+                'f' if self.is_keyword("false") => self.deserialize_bool(visitor),
                 '"' => self.deserialize_str(visitor),
-                '0'...'9' | '-' => {
-                    let mut s = String::new();
-                    let mut i = 0;
-                    loop {
-                        if DIGIT_END.contains(self.input.chars().nth(i).unwrap()) {
-                            if s.contains("e") {
-                                return self.deserialize_f64(visitor);
-                            } else if s.contains('.') {
-                                return self.deserialize_f64(visitor);
-                            } else if s.contains('-') {
-                                return self.deserialize_i64(visitor);
-                            } else {
-                                return self.deserialize_u64(visitor);
-                            }
-                        } else {
-                            s.push(self.input.chars().nth(i).unwrap());
-                            i += 1;
-                        }
-                    }
-                },
+                '0'..='9' | '-' | '+' | '.' if self.raw_numbers => {
+                    let (_, len) =
+                        number::lex(self.input, &DIGIT_END).map_err(|e| self.error(e))?;
+                    let text = &self.input[..len];
+                    self.input = &self.input[len..];
+                    visitor.visit_enum(RawNumber(text))
+                }
+                '0'..='9' | '-' | '+' | '.' => self.deserialize_number(visitor),
                 '{' => {
                     if self.next_is_class {
                         self.next_is_class = false;
@@ -245,7 +537,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
                         self.deserialize_seq(visitor)
                     }
                 },
-                _ => Err(Error::Syntax),
+                _ => self.deserialize_str(visitor),
             }
         }
     }
@@ -261,87 +553,103 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i8(self.parse_signed()?)
+        self.deserialize_number(visitor)
     }
 
     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i16(self.parse_signed()?)
+        self.deserialize_number(visitor)
     }
 
     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i32(self.parse_signed()?)
+        self.deserialize_number(visitor)
     }
 
     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i64(self.parse_signed()?)
+        self.deserialize_number(visitor)
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u8(self.parse_unsigned()?)
+        self.deserialize_number(visitor)
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u16(self.parse_unsigned()?)
+        self.deserialize_number(visitor)
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u32(self.parse_unsigned()?)
+        self.deserialize_number(visitor)
     }
 
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u64(self.parse_unsigned()?)
+        self.deserialize_number(visitor)
     }
 
-    // Float parsing is stupidly hard.
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_f32(self.parse_float()?)
+        let input = self.input;
+        let v = self.parse_float()?;
+        if !(v as f32).is_finite() {
+            let text = &input[..input.len() - self.input.len()];
+            self.input = input;
+            return Err(self.error(ErrorKind::NumberOutOfRange(text.to_string())));
+        }
+        visitor.visit_f32(v as f32)
     }
 
-    // Float parsing is stupidly hard.
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_f32(self.parse_float()?)
+        visitor.visit_f64(self.parse_float()?)
     }
 
-    fn deserialize_char<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        // Parse a string, check that it is one character, call `visit_char`.
-        unimplemented!()
+        let start = self.offset();
+        let s = self.parse_string()?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(self.locate_at(
+                de::Error::invalid_value(de::Unexpected::Str(&s), &"a single character"),
+                start,
+            )),
+        }
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_str(self.parse_string()?)
+        match self.parse_string()? {
+            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Cow::Owned(s) => visitor.visit_string(s),
+        }
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
@@ -351,42 +659,42 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         self.deserialize_str(visitor)
     }
 
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value>
+    // Configs have no byte strings, bytes are read from an array of numbers.
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        self.deserialize_seq(visitor)
     }
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        self.deserialize_seq(visitor)
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        if self.input.starts_with("null") {
-            self.input = &self.input["null".len()..];
+        if self.parse_keyword("null") {
             visitor.visit_none()
         } else {
             visitor.visit_some(self)
         }
     }
 
-    // In Serde, unit means an anonymous value containing no data.
+    // In Serde, unit means an anonymous value containing no data. Configs
+    // can only write it as `null` in lenient mode.
     fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        if self.input.starts_with("null") {
-            self.input = &self.input["null".len()..];
+        if self.parse_keyword("null") {
             visitor.visit_unit()
         } else {
-            Err(Error::ExpectedNull)
+            Err(self.error(ErrorKind::ExpectedNull))
         }
     }
 
@@ -405,39 +713,63 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
 
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        if name == VALUE_TOKEN {
+            let surface = self.surface_declarations;
+            let raw = self.raw_numbers;
+            self.surface_declarations = true;
+            self.raw_numbers = true;
+            let value = visitor.visit_newtype_struct(&mut *self);
+            self.surface_declarations = surface;
+            self.raw_numbers = raw;
+            return value;
+        }
         visitor.visit_newtype_struct(self)
     }
 
-    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        // A class, or the root, is read as a sequence of enums named after
+        // its entries.
+        let root = self.root;
+        self.root = false;
+        if root || self.next_is_class {
+            self.next_is_class = false;
+            if !root {
+                if self.peek_char() != '{' {
+                    return Err(self.error(ErrorKind::ExpectedMap));
+                }
+                self.next_char()?;
+            }
+            let value = visitor.visit_seq(VariantEntries::new(self, !root))?;
+            if !root {
+                self.skip_whitespace()?;
+                self.next_char()?;
+            }
+            return Ok(value);
+        }
         // Parse the opening bracket of the sequence.
-        self.next_is_class = false;
-        if self.next_char()? == '{' {
+        if self.peek_char() == '{' {
+            self.next_char()?;
             // Give the visitor access to each element of the sequence.
-            let value = visitor.visit_seq(CommaSeparated::new(&mut self))?;
+            let value = visitor.visit_seq(CommaSeparated::new(self))?;
             // Parse the closing bracket of the sequence.
-            loop {
-                if crate::WHITESPACE.contains(self.peek_char()) {
-                    self.next_char()?;
-                } else {
-                    break;
-                }
-            }
-            if self.next_char()? == '}' {
+            self.skip_whitespace()?;
+            if self.peek_char() == '}' {
+                self.next_char()?;
                 Ok(value)
             } else {
-                Err(Error::ExpectedArrayEnd)
+                Err(self.error(ErrorKind::ExpectedArrayEnd))
             }
         } else {
-            Err(Error::ExpectedArray)
+            Err(self.error(ErrorKind::ExpectedArray))
         }
     }
 
@@ -460,81 +792,74 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         self.deserialize_seq(visitor)
     }
 
-    fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        // The root of a config is a class body without braces.
+        if self.root {
+            return self.deserialize_struct("", &[], visitor);
+        }
         self.next_is_class = false;
-        if self.next_char()? == '{' {
-            let value = visitor.visit_map(ArmaClass::new(&mut self))?;
-            loop {
-                if WHITESPACE.contains(self.peek_char()) {
-                    self.next_char()?;
-                } else {
-                    break;
-                }
-            }
-            if self.peek_char() == '}' {
-                self.next_char()?;
-            }
-            Ok(value)
+        let parent = self.parent.take();
+        if self.peek_char() == '{' {
+            self.next_char()?;
+            visitor.visit_map(ArmaClass::new(self, parent, &[], true))
         } else {
-            Err(Error::ExpectedMap)
+            Err(self.error(ErrorKind::ExpectedMap))
         }
     }
 
     fn deserialize_struct<V>(
-        mut self,
+        self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        if self.peek_char() == '{' {
+        let braced = self.peek_char() == '{';
+        if braced {
             self.next_char()?;
         }
+        self.root = false;
         self.next_is_class = false;
-        let value = visitor.visit_map(ArmaClass::new(&mut self));
-        loop {
-            if WHITESPACE.contains(self.peek_char()) {
-                self.next_char()?;
-            } else {
-                break;
-            }
-        }
-        if self.peek_char() == '}' {
-            self.next_char();
-        }
-        value
+        let parent = self.parent.take();
+        visitor.visit_map(ArmaClass::new(self, parent, fields, braced))
     }
 
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
         _variants: &'static [&'static str],
-        _visitor: V,
+        visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!();
-        /*if self.peek_char() == '"' {
-            // Visit a unit variant.
-            visitor.visit_enum(self.parse_string()?.into_deserializer())
-        } else if self.next_char()? == '{' {
-            // Visit a newtype variant, tuple variant, or struct variant.
-            let value = visitor.visit_enum(Enum::new(self))?;
-            // Parse the matching close brace.
-            if self.next_char()? == '}' {
-                Ok(value)
-            } else {
-                Err(Error::ExpectedMapEnd)
+        if self.next_is_class && self.peek_char() == '{' {
+            // Visit a newtype variant, tuple variant, or struct variant, which
+            // are written as a class holding a single entry named after the
+            // variant.
+            self.next_is_class = false;
+            self.parent = None;
+            self.next_char()?;
+            self.skip_whitespace()?;
+            let value = visitor.visit_enum(VariantClass::new(self))?;
+            self.skip_whitespace()?;
+            if self.peek_char() != '}' {
+                return Err(self.error(ErrorKind::ExpectedMapEnd));
             }
+            self.next_char()?;
+            Ok(value)
+        } else if self.peek_char() == '{' {
+            Err(self.error(ErrorKind::ExpectedEnum))
         } else {
-            Err(Error::ExpectedEnum)
-        }*/
+            // Visit a unit variant, quoted or not.
+            let variant = self.parse_string()?;
+            visitor.visit_enum(variant.into_deserializer())
+        }
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
@@ -0,0 +1,159 @@
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{self, DeserializeSeed, EnumAccess, VariantAccess, Visitor};
+
+use crate::class::NUMBER_TOKEN;
+use crate::error::{Error, ErrorKind, Result};
+
+/// A number as it was written, before it is given to a visitor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Literal {
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+}
+
+impl Literal {
+    pub(crate) fn as_f64(self) -> f64 {
+        match self {
+            Literal::Unsigned(n) => n as f64,
+            Literal::Signed(n) => n as f64,
+            Literal::Float(n) => n,
+        }
+    }
+}
+
+// Reads the number at the start of `input`, which ends at the first character
+// of `end`. Accepts an optional sign, hexadecimal integers like `0x1F`, and
+// decimals with or without digits before the point like `.5`, `1.` and
+// `1e-006`. Returns the literal and how many bytes it takes up.
+pub(crate) fn lex(input: &str, end: &str) -> std::result::Result<(Literal, usize), ErrorKind> {
+    let len = input.find(|c| end.contains(c)).unwrap_or(input.len());
+    let text = &input[..len];
+    let invalid = || ErrorKind::InvalidNumber(text.to_string());
+    let out_of_range = || ErrorKind::NumberOutOfRange(text.to_string());
+
+    let (negative, unsigned) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+
+    let hex = unsigned
+        .strip_prefix("0x")
+        .or_else(|| unsigned.strip_prefix("0X"));
+    let magnitude = if let Some(digits) = hex {
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        u64::from_str_radix(digits, 16).map_err(|_| out_of_range())?
+    } else {
+        let bytes = unsigned.as_bytes();
+        let mut i = 0;
+        let integer = digits(bytes, &mut i);
+        let mut fraction = 0;
+        if bytes.get(i) == Some(&b'.') {
+            i += 1;
+            fraction = digits(bytes, &mut i);
+        }
+        if integer == 0 && fraction == 0 {
+            return Err(invalid());
+        }
+        let mut exponent = false;
+        if let Some(b'e') | Some(b'E') = bytes.get(i) {
+            i += 1;
+            if let Some(b'+') | Some(b'-') = bytes.get(i) {
+                i += 1;
+            }
+            if digits(bytes, &mut i) == 0 {
+                return Err(invalid());
+            }
+            exponent = true;
+        }
+        if i != bytes.len() {
+            return Err(invalid());
+        }
+        if fraction > 0 || exponent || unsigned.contains('.') {
+            let n: f64 = unsigned.parse().map_err(|_| invalid())?;
+            if !n.is_finite() {
+                return Err(out_of_range());
+            }
+            return Ok((Literal::Float(if negative { -n } else { n }), len));
+        }
+        unsigned.parse().map_err(|_| out_of_range())?
+    };
+
+    let literal = if !negative {
+        Literal::Unsigned(magnitude)
+    } else if magnitude <= i64::MAX as u64 {
+        Literal::Signed(-(magnitude as i64))
+    } else if magnitude == i64::MAX as u64 + 1 {
+        Literal::Signed(i64::MIN)
+    } else {
+        return Err(out_of_range());
+    };
+    Ok((literal, len))
+}
+
+// Skips ASCII digits starting at `i`, returns how many there were.
+fn digits(bytes: &[u8], i: &mut usize) -> usize {
+    let start = *i;
+    while bytes.get(*i).is_some_and(u8::is_ascii_digit) {
+        *i += 1;
+    }
+    *i - start
+}
+
+// Gives a number to `Value` as the newtype variant `NUMBER_TOKEN` holding the
+// text of the literal, so it can keep it.
+pub(crate) struct RawNumber<'de>(pub(crate) &'de str);
+
+impl<'de> EnumAccess<'de> for RawNumber<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(BorrowedStrDeserializer::<Error>::new(NUMBER_TOKEN))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for RawNumber<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Err(de::Error::invalid_type(
+            de::Unexpected::NewtypeVariant,
+            &"a unit variant",
+        ))
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(BorrowedStrDeserializer::new(self.0))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(
+            de::Unexpected::NewtypeVariant,
+            &"a tuple variant",
+        ))
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(
+            de::Unexpected::NewtypeVariant,
+            &"a struct variant",
+        ))
+    }
+}
@@ -0,0 +1,601 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+
+use crate::diagnostic::Diagnostic;
+use crate::error::{Error, ErrorKind, Position, Result};
+use crate::source_map::SourceMap;
+
+/// Where the preprocessor reads files from. Implemented for maps from file
+/// name to contents, which is handy in tests.
+pub trait FileSystem {
+    /// The name of the file `#include "path"` refers to when it is written in
+    /// the file `from`.
+    fn resolve(&self, path: &str, from: &str) -> io::Result<String>;
+
+    /// The contents of a file named by `resolve`.
+    fn read(&self, name: &str) -> io::Result<String>;
+}
+
+impl<F: FileSystem + ?Sized> FileSystem for &F {
+    fn resolve(&self, path: &str, from: &str) -> io::Result<String> {
+        (**self).resolve(path, from)
+    }
+
+    fn read(&self, name: &str) -> io::Result<String> {
+        (**self).read(name)
+    }
+}
+
+// Map keys are paths separated by `/`. An include is looked up next to the
+// including file first and then from the root.
+macro_rules! map_file_system {
+    ($map:ident) => {
+        impl FileSystem for $map<String, String> {
+            fn resolve(&self, path: &str, from: &str) -> io::Result<String> {
+                let relative = join(from, path);
+                if self.contains_key(&relative) {
+                    return Ok(relative);
+                }
+                let absolute = join("", path);
+                if self.contains_key(&absolute) {
+                    return Ok(absolute);
+                }
+                Err(io::ErrorKind::NotFound.into())
+            }
+
+            fn read(&self, name: &str) -> io::Result<String> {
+                self.get(name)
+                    .cloned()
+                    .ok_or_else(|| io::ErrorKind::NotFound.into())
+            }
+        }
+    };
+}
+
+map_file_system!(HashMap);
+map_file_system!(BTreeMap);
+
+// `path` written in the file `from`, with `\` turned into `/` and `.` and `..`
+// resolved. Paths starting with a separator are taken from the root.
+pub(crate) fn join(from: &str, path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let dir = match from.rsplit_once('/') {
+        Some((dir, _)) if !path.starts_with('/') => dir,
+        _ => "",
+    };
+    let mut parts = Vec::new();
+    for part in dir.split('/').chain(path.split('/')) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// The text a `Preprocessor` produced and where it came from.
+#[derive(Clone, Debug)]
+pub struct Preprocessed {
+    text: String,
+    map: SourceMap,
+}
+
+impl Preprocessed {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn source_map(&self) -> &SourceMap {
+        &self.map
+    }
+
+    /// Moves the position of an error raised while reading the text to the
+    /// file and line it was written on, see `SourceMap::translate`.
+    pub fn translate(&self, err: Error) -> Error {
+        self.map.translate(err)
+    }
+
+    /// A `Diagnostic` that shows a translated error in the file it is in.
+    pub fn diagnostic<'a>(&'a self, err: &'a Error) -> Diagnostic<'a> {
+        let source = err.file().and_then(|f| self.map.source(f));
+        Diagnostic::new(err, source.unwrap_or(&self.text))
+    }
+}
+
+// A point in the expanded text of `Preprocessor::expand`, the offset in its
+// input it comes from and the macros being expanded there.
+struct Mark {
+    output: usize,
+    source: usize,
+    macros: Vec<String>,
+}
+
+struct Macro {
+    // `None` for object-like macros.
+    params: Option<Vec<String>>,
+    body: String,
+}
+
+/// Runs configs through the preprocessor the game applies before reading
+/// them. Supports `#include`, object-like and function-like `#define`s with
+/// the `#` and `##` operators, `#undef`, and `#ifdef`, `#ifndef`, `#else` and
+/// `#endif`.
+///
+/// Like the game, arguments are expanded before they are substituted, also
+/// where they are stringified or pasted, so `QUOTE(ADDON)` gives the name of
+/// the addon. Comments are removed and every directive leaves an empty line
+/// behind, so lines outside of includes keep their numbers. The
+/// `SourceMap` of the result leads back to the files and macros.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use serde_arma::Preprocessor;
+///
+/// let mut files = HashMap::new();
+/// files.insert("config.cpp".to_string(), "#include \"macros.hpp\"\nname = QUOTE(ADDON);".to_string());
+/// files.insert("macros.hpp".to_string(), "#define ADDON main\n#define QUOTE(x) #x".to_string());
+/// let preprocessed = Preprocessor::new(files).process("config.cpp").unwrap();
+/// assert_eq!("\nname = \"main\";", preprocessed.text());
+/// ```
+pub struct Preprocessor<F> {
+    fs: F,
+    macros: HashMap<String, Macro>,
+    map: SourceMap,
+    // The files being included as indices into `map`, innermost last.
+    files: Vec<usize>,
+}
+
+impl<F: FileSystem> Preprocessor<F> {
+    pub fn new(fs: F) -> Self {
+        Preprocessor {
+            fs,
+            macros: HashMap::new(),
+            map: SourceMap::default(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Defines an object-like macro before any file is read, like `-D` on a
+    /// compiler's command line.
+    pub fn define(mut self, name: &str, body: &str) -> Self {
+        self.macros.insert(
+            name.to_string(),
+            Macro {
+                params: None,
+                body: body.to_string(),
+            },
+        );
+        self
+    }
+
+    /// Whether `name` is defined, for example after processing a file.
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.macros.contains_key(name)
+    }
+
+    /// Reads `file` from the file system and preprocesses it.
+    pub fn process(&mut self, file: &str) -> Result<Preprocessed> {
+        let source = self
+            .fs
+            .read(file)
+            .map_err(|_| Error::from(ErrorKind::IncludeNotFound(file.to_string())))?;
+        self.process_str(file, &source)
+    }
+
+    /// Preprocesses `source`, includes are looked up relative to `file`.
+    pub fn process_str(&mut self, file: &str, source: &str) -> Result<Preprocessed> {
+        let mut text = String::new();
+        self.map = SourceMap::default();
+        self.process_file(file, source, &mut text)?;
+        Ok(Preprocessed {
+            text,
+            map: std::mem::take(&mut self.map),
+        })
+    }
+
+    fn process_file(&mut self, file: &str, source: &str, out: &mut String) -> Result<()> {
+        self.files.push(self.map.add_file(file, source));
+        let result = self.run(source, out);
+        self.files.pop();
+        result.map_err(|e| e.or_file(file))
+    }
+
+    // The index of the file being read in `map`.
+    fn file(&self) -> usize {
+        self.files.last().copied().unwrap_or(0)
+    }
+
+    fn run(&mut self, source: &str, out: &mut String) -> Result<()> {
+        let text = strip_comments(source);
+        let file = self.file();
+        let error_at = |kind, offset| Error::new(kind, Some(Position::from_offset(&text, offset)));
+        // Whether each open `#ifdef` takes the current branch, and whether
+        // it is in its `#else`.
+        let mut conditions: Vec<(bool, bool)> = Vec::new();
+        // The start of the text that is waiting to be expanded.
+        let mut pending = 0;
+        let mut offset = 0;
+        while offset < text.len() {
+            let mut end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+            let active = conditions.iter().all(|c| c.0);
+            let directive = text[offset..end].trim_start().starts_with('#');
+            if !directive && active {
+                offset = (end + 1).min(text.len());
+                continue;
+            }
+            self.expand_at(&text, pending, offset, out)?;
+            if !directive {
+                offset = (end + 1).min(text.len());
+                pending = offset;
+                if end < text.len() {
+                    self.map.add_segment(out.len(), file, end, Vec::new());
+                    out.push('\n');
+                }
+                continue;
+            }
+
+            // A directive continues on the next line after a `\`.
+            while text[offset..end].trim_end_matches('\r').ends_with('\\') && end < text.len() {
+                end = text[end + 1..]
+                    .find('\n')
+                    .map_or(text.len(), |i| end + 1 + i);
+            }
+            let line = text[offset..end].replace("\\\r\n", "").replace("\\\n", "");
+            let line = line.trim().trim_start_matches('#').trim_start();
+            let name_len = ident_len(line);
+            let (name, rest) = (&line[..name_len], line[name_len..].trim());
+            let hash = offset + text[offset..end].find('#').unwrap_or(0);
+            let error = |kind| error_at(kind, hash);
+            let newlines = text[offset..end].matches('\n').count() + usize::from(end < text.len());
+
+            match name {
+                "ifdef" | "ifndef" => {
+                    let word = &rest[..ident_len(rest)];
+                    if word.is_empty() {
+                        let name = if name == "ifdef" { "ifdef" } else { "ifndef" };
+                        return Err(error(ErrorKind::InvalidDirective(name)));
+                    }
+                    let defined = self.macros.contains_key(word);
+                    conditions.push((defined == (name == "ifdef"), false));
+                }
+                "else" => match conditions.last_mut() {
+                    Some(c) if !c.1 => *c = (!c.0, true),
+                    _ => return Err(error(ErrorKind::InvalidDirective("else"))),
+                },
+                "endif" => {
+                    if conditions.pop().is_none() {
+                        return Err(error(ErrorKind::InvalidDirective("endif")));
+                    }
+                }
+                // `#if` isn't supported, but in a skipped branch it still
+                // opens a conditional that its `#endif` closes.
+                _ if !active && name.starts_with("if") => conditions.push((false, false)),
+                _ if !active => {}
+                "define" => {
+                    let (name, definition) = parse_define(rest)
+                        .ok_or_else(|| error(ErrorKind::InvalidDirective("define")))?;
+                    self.macros.insert(name.to_string(), definition);
+                }
+                "undef" => {
+                    let word = &rest[..ident_len(rest)];
+                    if word.is_empty() {
+                        return Err(error(ErrorKind::InvalidDirective("undef")));
+                    }
+                    self.macros.remove(word);
+                }
+                "include" => {
+                    let path = include_path(rest)
+                        .ok_or_else(|| error(ErrorKind::InvalidDirective("include")))?;
+                    self.include(path, out)
+                        .map_err(|e| e.or_position(Position::from_offset(&text, hash)))?;
+                    self.map.add_segment(out.len(), file, end, Vec::new());
+                    if newlines > 0 && !out.ends_with('\n') {
+                        out.push('\n');
+                    }
+                    offset = (end + 1).min(text.len());
+                    pending = offset;
+                    out.push_str(&"\n".repeat(newlines.saturating_sub(1)));
+                    continue;
+                }
+                _ => return Err(error(ErrorKind::UnknownDirective(name.to_string()))),
+            }
+            self.map.add_segment(out.len(), file, hash, Vec::new());
+            out.push_str(&"\n".repeat(newlines));
+            offset = (end + 1).min(text.len());
+            pending = offset;
+        }
+        if !conditions.is_empty() {
+            return Err(error_at(ErrorKind::UnterminatedConditional, text.len()));
+        }
+        self.expand_at(&text, pending, text.len(), out)
+    }
+
+    fn include(&mut self, path: &str, out: &mut String) -> Result<()> {
+        let from = self.map.file_name(self.file());
+        let not_found = || Error::from(ErrorKind::IncludeNotFound(path.to_string()));
+        let name = self.fs.resolve(path, from).map_err(|_| not_found())?;
+        if self.files.iter().any(|&f| self.map.file_name(f) == name) {
+            return Err(ErrorKind::RecursiveInclude(name).into());
+        }
+        let source = self.fs.read(&name).map_err(|_| not_found())?;
+        self.process_file(&name, &source, out)
+    }
+
+    // Expands the macros in `text[start..end]` onto `out` and maps the
+    // result. Errors are placed at the invocation they come from.
+    fn expand_at(&mut self, text: &str, start: usize, end: usize, out: &mut String) -> Result<()> {
+        let mut marks = vec![Mark {
+            output: out.len(),
+            source: 0,
+            macros: Vec::new(),
+        }];
+        self.expand(&text[start..end], None, &mut Vec::new(), out, &mut marks)
+            .map_err(|(e, at)| e.or_position(Position::from_offset(text, start + at)))?;
+        let file = self.file();
+        for mark in marks {
+            self.map
+                .add_segment(mark.output, file, start + mark.source, mark.macros);
+        }
+        Ok(())
+    }
+
+    // Replaces the macros in `text` onto `out`, except for the ones in
+    // `active` which are being expanded already. `origin` is the offset of
+    // the outermost invocation when `text` is the body of a macro. Errors
+    // come with the offset of the outermost invocation in `text`.
+    fn expand(
+        &self,
+        text: &str,
+        origin: Option<usize>,
+        active: &mut Vec<String>,
+        out: &mut String,
+        marks: &mut Vec<Mark>,
+    ) -> std::result::Result<(), (Error, usize)> {
+        let mut i = 0;
+        while i < text.len() {
+            let rest = &text[i..];
+            let c = rest.chars().next().unwrap_or(' ');
+            if c == '"' {
+                let len = string_len(rest);
+                out.push_str(&rest[..len]);
+                i += len;
+                continue;
+            }
+            if !is_ident_start(c) {
+                // Skips the rest of words that start with a digit, like `1e5`.
+                let len = if c.is_ascii_digit() {
+                    ident_len(rest).max(1)
+                } else {
+                    c.len_utf8()
+                };
+                out.push_str(&rest[..len]);
+                i += len;
+                continue;
+            }
+
+            let len = ident_len(rest);
+            let name = &rest[..len];
+            let start = i;
+            let at = origin.unwrap_or(start);
+            i += len;
+            let definition = match self.macros.get(name) {
+                Some(m) if !active.iter().any(|a| a == name) => m,
+                _ => {
+                    out.push_str(name);
+                    continue;
+                }
+            };
+            let body = match definition.params {
+                None => definition.body.clone(),
+                Some(ref params) => {
+                    let after = text[i..].trim_start();
+                    if !after.starts_with('(') {
+                        out.push_str(name);
+                        continue;
+                    }
+                    i = text.len() - after.len() + 1;
+                    let (args, len) = split_args(&text[i..])
+                        .ok_or_else(|| (ErrorKind::UnexpectedEof.into(), start))?;
+                    i += len;
+                    let args = match args.as_slice() {
+                        [arg] if params.is_empty() && arg.is_empty() => Vec::new(),
+                        _ => args,
+                    };
+                    if args.len() != params.len() {
+                        let kind = ErrorKind::MacroArguments(name.to_string(), params.len());
+                        return Err((kind.into(), start));
+                    }
+                    let mut expanded = Vec::with_capacity(args.len());
+                    for arg in &args {
+                        let mut text = String::new();
+                        self.expand(arg, Some(at), active, &mut text, &mut Vec::new())
+                            .map_err(|(e, _)| (e, start))?;
+                        expanded.push(text);
+                    }
+                    substitute(&definition.body, params, &expanded)
+                }
+            };
+            active.push(name.to_string());
+            marks.push(Mark {
+                output: out.len(),
+                source: at,
+                macros: active.clone(),
+            });
+            let expanded = self.expand(&body, Some(at), active, out, marks);
+            active.pop();
+            expanded.map_err(|(e, _)| (e, start))?;
+            marks.push(Mark {
+                output: out.len(),
+                source: origin.unwrap_or(i),
+                macros: active.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+// `NAME body` or `NAME(a, b) body`.
+fn parse_define(rest: &str) -> Option<(&str, Macro)> {
+    let len = ident_len(rest);
+    if len == 0 {
+        return None;
+    }
+    let (name, rest) = rest.split_at(len);
+    let (params, body) = match rest.strip_prefix('(') {
+        Some(rest) => {
+            let (list, body) = rest.split_once(')')?;
+            let params: Vec<String> = if list.trim().is_empty() {
+                Vec::new()
+            } else {
+                list.split(',').map(|p| p.trim().to_string()).collect()
+            };
+            if params
+                .iter()
+                .any(|p| p.is_empty() || ident_len(p) != p.len())
+            {
+                return None;
+            }
+            (Some(params), body)
+        }
+        None => (None, rest),
+    };
+    let body = body.trim().to_string();
+    Some((name, Macro { params, body }))
+}
+
+// `"path"` or `<path>`.
+fn include_path(rest: &str) -> Option<&str> {
+    let inner = rest
+        .strip_prefix('"')
+        .and_then(|r| r.strip_suffix('"'))
+        .or_else(|| rest.strip_prefix('<').and_then(|r| r.strip_suffix('>')))?;
+    Some(inner).filter(|p| !p.is_empty())
+}
+
+// The arguments of an invocation, up to the `)` that closes it, and the
+// length they take up including the `)`. Commas inside parentheses and
+// strings don't separate arguments.
+fn split_args(text: &str) -> Option<(Vec<String>, usize)> {
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut arg_start = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let c = text[i..].chars().next()?;
+        match c {
+            '"' => {
+                i += string_len(&text[i..]);
+                continue;
+            }
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                args.push(text[arg_start..i].trim().to_string());
+                return Some((args, i + 1));
+            }
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(text[arg_start..i].trim().to_string());
+                arg_start = i + 1;
+            }
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+    None
+}
+
+// The body of a function-like macro with its parameters replaced. `#param`
+// puts the argument in quotes and `##` joins what is on either side of it.
+fn substitute(body: &str, params: &[String], args: &[String]) -> String {
+    let arg = |name: &str| params.iter().position(|p| p == name).map(|i| &args[i]);
+    let mut out = String::with_capacity(body.len());
+    let mut i = 0;
+    while i < body.len() {
+        let rest = &body[i..];
+        let c = rest.chars().next().unwrap_or(' ');
+        if c == '"' {
+            let len = string_len(rest);
+            out.push_str(&rest[..len]);
+            i += len;
+        } else if rest.starts_with("##") {
+            out.truncate(out.trim_end().len());
+            i += 2;
+            i += body[i..].len() - body[i..].trim_start().len();
+        } else if c == '#' && arg(&rest[1..1 + ident_len(&rest[1..])]).is_some() {
+            let len = ident_len(&rest[1..]);
+            out.push('"');
+            out.push_str(arg(&rest[1..1 + len]).map_or("", String::as_str));
+            out.push('"');
+            i += 1 + len;
+        } else if is_ident_start(c) || c.is_ascii_digit() {
+            let len = ident_len(rest);
+            out.push_str(arg(&rest[..len]).map_or(&rest[..len], String::as_str));
+            i += len;
+        } else {
+            out.push(c);
+            i += c.len_utf8();
+        }
+    }
+    out
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+// The length of the identifier, or the word of digits and letters, at the
+// start of `text`.
+fn ident_len(text: &str) -> usize {
+    text.find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(text.len())
+}
+
+// The length of the quoted string at the start of `text`. Strings can't span
+// lines, an unterminated one ends at the line break.
+fn string_len(text: &str) -> usize {
+    match text[1..].find(['"', '\n']) {
+        Some(i) if text[1 + i..].starts_with('"') => i + 2,
+        Some(i) => i + 1,
+        None => text.len(),
+    }
+}
+
+// Replaces comments with spaces, keeping their line breaks, so offsets into
+// the result are offsets into `source`.
+fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let blank = |out: &mut String, text: &str| {
+        for c in text.chars() {
+            if c == '\n' {
+                out.push('\n');
+            } else {
+                out.extend(std::iter::repeat_n(' ', c.len_utf8()));
+            }
+        }
+    };
+    let mut i = 0;
+    while i < source.len() {
+        let rest = &source[i..];
+        if rest.starts_with('"') {
+            let len = string_len(rest);
+            out.push_str(&rest[..len]);
+            i += len;
+        } else if rest.starts_with("//") {
+            let len = rest.find('\n').unwrap_or(rest.len());
+            blank(&mut out, &rest[..len]);
+            i += len;
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let len = comment.find("*/").map_or(rest.len(), |end| end + 4);
+            blank(&mut out, &rest[..len]);
+            i += len;
+        } else {
+            let c = rest.chars().next().unwrap_or(' ');
+            out.push(c);
+            i += c.len_utf8();
+        }
+    }
+    out
+}
@@ -0,0 +1,119 @@
+use crate::error::{ErrorKind, Result};
+use crate::value::{Class, Value};
+
+type Entries = Vec<(String, Value)>;
+
+impl Class {
+    /// Returns a copy of the class where every class includes the entries it
+    /// inherits, so values defined on base classes can be read directly.
+    ///
+    /// A parent is looked up the way the game does it: first among the
+    /// entries of the enclosing class that are defined before the child,
+    /// including the ones the enclosing class inherits itself, then in each
+    /// enclosing scope further out.
+    pub fn resolve(&self) -> Result<Class> {
+        let entries = resolve_entries(&self.entries, Vec::new(), &[])?;
+        Ok(Class {
+            parent: self.parent.clone(),
+            entries,
+        })
+    }
+}
+
+impl Value {
+    /// Resolves inheritance if the value is a class, see `Class::resolve`.
+    pub fn resolve(&self) -> Result<Value> {
+        match *self {
+            Value::Class(ref c) => c.resolve().map(Value::Class),
+            ref other => Ok(other.clone()),
+        }
+    }
+}
+
+// `inherited` are the already resolved entries of the class's parent, `scopes`
+// the resolved entries of every enclosing class, innermost last.
+fn resolve_entries(
+    own: &[(String, Value)],
+    inherited: Entries,
+    scopes: &[&Entries],
+) -> Result<Entries> {
+    let mut out = inherited;
+    for (key, value) in own {
+        let value = match *value {
+            Value::Class(ref class) => {
+                let inherited = match class.parent {
+                    Some(ref parent) => lookup(parent, &out, scopes)?,
+                    None => Vec::new(),
+                };
+                let mut inner = scopes.to_vec();
+                inner.push(&out);
+                Value::Class(Class {
+                    parent: class.parent.clone(),
+                    entries: resolve_entries(&class.entries, inherited, &inner)?,
+                })
+            }
+            // A declaration does not replace a class that is already known.
+            Value::External => {
+                if !out.iter().any(|(k, _)| k.eq_ignore_ascii_case(key)) {
+                    out.push((key.clone(), Value::External));
+                }
+                continue;
+            }
+            Value::Delete => {
+                out.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+                continue;
+            }
+            Value::Append(ref items) => {
+                let mut array = inherited_array(&out, key);
+                array.extend(items.iter().cloned());
+                Value::Array(array)
+            }
+            Value::Remove(ref items) => {
+                let mut array = inherited_array(&out, key);
+                array.retain(|element| !items.contains(element));
+                Value::Array(array)
+            }
+            ref other => other.clone(),
+        };
+        insert(&mut out, key, value);
+    }
+    Ok(out)
+}
+
+// Classes that are only declared with `class Name;` are defined outside of the
+// config being resolved, so nothing can be inherited from them.
+fn lookup<'a>(name: &str, siblings: &'a Entries, scopes: &[&'a Entries]) -> Result<Entries> {
+    std::iter::once(siblings)
+        .chain(scopes.iter().rev().cloned())
+        .filter_map(|entries| {
+            entries
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .and_then(|(_, v)| match *v {
+                    Value::Class(ref class) => Some(class.entries.clone()),
+                    Value::External => Some(Vec::new()),
+                    _ => None,
+                })
+        })
+        .next()
+        .ok_or_else(|| ErrorKind::UnresolvedParent(name.to_string()).into())
+}
+
+fn inherited_array(entries: &Entries, key: &str) -> Vec<Value> {
+    entries
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .and_then(|(_, v)| v.as_array())
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn insert(entries: &mut Entries, key: &str, value: Value) {
+    match entries
+        .iter_mut()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+    {
+        Some(existing) => *existing = (key.to_string(), value),
+        None => entries.push((key.to_string(), value)),
+    }
+}
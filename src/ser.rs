@@ -0,0 +1,1300 @@
+use std::fmt;
+use std::io;
+
+use serde::ser::{self, Serialize};
+
+use crate::class::{DECLARATION_TOKEN, NUMBER_TOKEN, OPERATOR_TOKEN, PARENT_KEY};
+use crate::error::{Error, ErrorKind, Result};
+
+/// How floating point numbers are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FloatFormat {
+    /// The shortest representation that reads back to the same value, with
+    /// an exponent for very large and very small magnitudes. Whole numbers
+    /// get a `.0` so they are read back as floats.
+    Shortest,
+    /// Eight significant digits with a three digit exponent, like
+    /// `-1.5593832e-007`, as written by the game and its tools. Whole
+    /// numbers get a `.0` so they are read back as floats.
+    Bohemia,
+}
+
+/// Controls the layout of serialized configs.
+#[derive(Clone, Debug)]
+pub struct PrettyConfig {
+    indent: String,
+    brace_on_new_line: bool,
+    space_around_equals: bool,
+    space_after_comma: bool,
+    array_inline_limit: Option<usize>,
+    float_format: FloatFormat,
+}
+
+impl PrettyConfig {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The style used by `mission.sqm` files saved from the editor.
+    pub fn bohemia() -> Self {
+        PrettyConfig {
+            indent: "\t".to_string(),
+            brace_on_new_line: true,
+            space_around_equals: false,
+            space_after_comma: false,
+            array_inline_limit: Some(3),
+            float_format: FloatFormat::Bohemia,
+        }
+    }
+
+    /// The string used for one level of indentation, for example `"\t"` or
+    /// four spaces.
+    pub fn indent(mut self, indent: &str) -> Self {
+        self.indent = indent.to_string();
+        self
+    }
+
+    /// Put the `{` of classes and multi-line arrays on its own line.
+    pub fn brace_on_new_line(mut self, value: bool) -> Self {
+        self.brace_on_new_line = value;
+        self
+    }
+
+    /// Write `key = value;` instead of `key=value;`.
+    pub fn space_around_equals(mut self, value: bool) -> Self {
+        self.space_around_equals = value;
+        self
+    }
+
+    /// Write `{1, 2}` instead of `{1,2}`.
+    pub fn space_after_comma(mut self, value: bool) -> Self {
+        self.space_after_comma = value;
+        self
+    }
+
+    /// Arrays with more elements than the limit are written one element per
+    /// line. `None` keeps every array on a single line.
+    pub fn array_inline_limit(mut self, limit: Option<usize>) -> Self {
+        self.array_inline_limit = limit;
+        self
+    }
+
+    pub fn float_format(mut self, format: FloatFormat) -> Self {
+        self.float_format = format;
+        self
+    }
+}
+
+impl Default for PrettyConfig {
+    fn default() -> Self {
+        PrettyConfig {
+            indent: "    ".to_string(),
+            brace_on_new_line: false,
+            space_around_equals: true,
+            space_after_comma: true,
+            array_inline_limit: None,
+            float_format: FloatFormat::Shortest,
+        }
+    }
+}
+
+pub struct Serializer<W> {
+    writer: W,
+    config: PrettyConfig,
+    depth: usize,
+    operator: Option<&'static str>,
+}
+
+impl<W: io::Write> Serializer<W> {
+    pub fn new(writer: W) -> Self {
+        Serializer::with_config(writer, PrettyConfig::default())
+    }
+
+    pub fn with_config(writer: W, config: PrettyConfig) -> Self {
+        Serializer {
+            writer,
+            config,
+            depth: 0,
+            operator: None,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write(&mut self, s: &str) -> Result<()> {
+        self.write_bytes(s.as_bytes())
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.writer
+            .write_all(bytes)
+            .map_err(|e| Error::from(ErrorKind::Io(e.to_string())))
+    }
+
+    fn write_indent(&mut self) -> Result<()> {
+        for _ in 0..self.depth {
+            self.writer
+                .write_all(self.config.indent.as_bytes())
+                .map_err(|e| Error::from(ErrorKind::Io(e.to_string())))?;
+        }
+        Ok(())
+    }
+
+    // `operator` is empty for plain assignments, or `+` and `-` for arrays.
+    fn write_assignment(&mut self, operator: &str) -> Result<()> {
+        if self.config.space_around_equals {
+            self.write(" ")?;
+        }
+        self.write(operator)?;
+        self.write("=")?;
+        if self.config.space_around_equals {
+            self.write(" ")?;
+        }
+        Ok(())
+    }
+
+    fn write_open_brace(&mut self) -> Result<()> {
+        if self.config.brace_on_new_line {
+            self.write("\n")?;
+            self.write_indent()?;
+            self.write("{\n")
+        } else {
+            self.write(" {\n")
+        }
+    }
+
+    fn write_string(&mut self, v: &str) -> Result<()> {
+        // Quotes are escaped by doubling them, newlines can only be expressed
+        // by joining two strings with `\n`.
+        let escaped = v.replace('"', "\"\"").replace('\n', "\" \\n \"");
+        self.write("\"")?;
+        self.write(&escaped)?;
+        self.write("\"")
+    }
+
+    fn write_float<F>(&mut self, v: F) -> Result<()>
+    where
+        F: Into<f64> + ToString + fmt::LowerExp + Copy,
+    {
+        if !v.into().is_finite() {
+            return Err(ErrorKind::Unsupported("non-finite float").into());
+        }
+        match self.config.float_format {
+            FloatFormat::Shortest => self.write(&format_shortest(v)),
+            FloatFormat::Bohemia => self.write(&format_bohemia(v.into())),
+        }
+    }
+
+    fn open_class(&mut self, key: &str, parent: Option<&str>) -> Result<()> {
+        self.write_indent()?;
+        self.write("class ")?;
+        self.write(key)?;
+        if let Some(parent) = parent {
+            self.write(": ")?;
+            self.write(parent)?;
+        }
+        self.write_open_brace()?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn close_class(&mut self) -> Result<()> {
+        self.depth -= 1;
+        self.write_indent()?;
+        self.write("};\n")
+    }
+}
+
+fn format_shortest<F>(v: F) -> String
+where
+    F: Into<f64> + ToString + fmt::LowerExp + Copy,
+{
+    // Written out in full, `1e20` would be read back as an integer that
+    // doesn't fit and `1e-300` would take hundreds of digits.
+    let magnitude = v.into().abs();
+    if magnitude != 0.0 && !(1e-5..1e16).contains(&magnitude) {
+        return format!("{:e}", v);
+    }
+    let s = v.to_string();
+    if s.contains('.') {
+        s
+    } else {
+        s + ".0"
+    }
+}
+
+// Equivalent to C's `%.8g` with the three digit exponent of the MSVC runtime.
+fn format_bohemia(v: f64) -> String {
+    const PRECISION: usize = 8;
+    if v == 0.0 {
+        return "0.0".to_string();
+    }
+    // Rounding to the precision first gives the exponent `%g` decides on.
+    let scientific = format!("{:.*e}", PRECISION - 1, v);
+    let (mantissa, exponent) = scientific.split_at(scientific.find('e').unwrap());
+    let exponent: i32 = exponent[1..].parse().unwrap();
+    if exponent >= -4 && exponent < PRECISION as i32 {
+        let decimals = (PRECISION as i32 - 1 - exponent) as usize;
+        let fixed = format!("{:.*}", decimals, v);
+        match trim_fraction(&fixed) {
+            whole if !whole.contains('.') => format!("{}.0", whole),
+            trimmed => trimmed.to_string(),
+        }
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:03}", trim_fraction(mantissa), sign, exponent.abs())
+    }
+}
+
+fn trim_fraction(s: &str) -> &str {
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        s
+    }
+}
+
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: io::Write,
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::new(writer);
+    value.serialize(&mut serializer)
+}
+
+pub fn to_writer_pretty<W, T>(writer: W, value: &T, config: PrettyConfig) -> Result<()>
+where
+    W: io::Write,
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::with_config(writer, config);
+    value.serialize(&mut serializer)
+}
+
+pub fn to_string<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    to_string_pretty(value, PrettyConfig::default())
+}
+
+pub fn to_string_pretty<T>(value: &T, config: PrettyConfig) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let mut writer = Vec::new();
+    to_writer_pretty(&mut writer, value, config)?;
+    // The serializer only ever writes valid UTF-8.
+    Ok(String::from_utf8(writer).expect("serializer produced invalid UTF-8"))
+}
+
+// The top level of a config is the body of a class without the braces, so
+// only structs and maps can be serialized here.
+impl<'a, W: io::Write> ser::Serializer for &'a mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = ser::Impossible<(), Error>;
+    type SerializeTuple = ser::Impossible<(), Error>;
+    type SerializeTupleStruct = ser::Impossible<(), Error>;
+    type SerializeTupleVariant = ser::Impossible<(), Error>;
+    type SerializeMap = ClassSerializer<'a, W>;
+    type SerializeStruct = ClassSerializer<'a, W>;
+    type SerializeStructVariant = ser::Impossible<(), Error>;
+
+    fn serialize_bool(self, _v: bool) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_char(self, _v: char) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<()> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(ClassSerializer::new(self, None))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(ClassSerializer::new(self, None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(ErrorKind::ExpectedMap.into())
+    }
+}
+
+// Writes the entries of a class, closing the braces on `end` if the class was
+// opened by an `EntrySerializer`. The header of the class is only written once
+// the parent is known, entries that come before it are kept in `pending`.
+pub struct ClassSerializer<'a, W> {
+    ser: &'a mut Serializer<W>,
+    key: Option<String>,
+    header: Option<String>,
+    pending: Option<Serializer<Vec<u8>>>,
+    close: bool,
+}
+
+impl<'a, W: io::Write> ClassSerializer<'a, W> {
+    fn new(ser: &'a mut Serializer<W>, header: Option<&str>) -> Self {
+        ClassSerializer {
+            ser,
+            key: None,
+            header: header.map(String::from),
+            pending: None,
+            close: header.is_some(),
+        }
+    }
+
+    fn open(&mut self, parent: Option<&str>) -> Result<()> {
+        let name = match self.header.take() {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        self.ser.open_class(&name, parent)?;
+        match self.pending.take() {
+            Some(pending) => self.ser.write_bytes(&pending.writer),
+            None => Ok(()),
+        }
+    }
+
+    fn entry<T>(&mut self, key: &str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if key == PARENT_KEY {
+            if self.header.is_none() {
+                return Err(ErrorKind::Unsupported("parent outside of a class header").into());
+            }
+            let parent = value.serialize(KeySerializer)?;
+            return self.open(Some(parent.as_str()).filter(|p| !p.is_empty()));
+        }
+        if self.header.is_some() {
+            let ser = &*self.ser;
+            let pending = self.pending.get_or_insert_with(|| Serializer {
+                writer: Vec::new(),
+                config: ser.config.clone(),
+                depth: ser.depth + 1,
+                operator: None,
+            });
+            return value.serialize(EntrySerializer { ser: pending, key });
+        }
+        value.serialize(EntrySerializer {
+            ser: &mut *self.ser,
+            key,
+        })
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeMap for ClassSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = key.serialize(KeySerializer)?;
+        if key.is_empty() {
+            return Err(ErrorKind::KeyMustBeAString.into());
+        }
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::from(ErrorKind::ExpectedString))?;
+        self.entry(&key, value)
+    }
+
+    fn end(mut self) -> Result<()> {
+        self.open(None)?;
+        if self.close {
+            self.ser.close_class()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeStruct for ClassSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.entry(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeStructVariant for ClassSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(mut self) -> Result<()> {
+        // Struct variants are wrapped in a class named after the field and a
+        // class named after the variant.
+        self.open(None)?;
+        self.ser.close_class()?;
+        self.ser.close_class()
+    }
+}
+
+// Serializes a single `key = value;` entry. The shape of the entry depends on
+// the value: classes become `class key {...};` and arrays `key[] = {...};`.
+struct EntrySerializer<'a, 'k, W> {
+    ser: &'a mut Serializer<W>,
+    key: &'k str,
+}
+
+impl<'a, 'k, W: io::Write> EntrySerializer<'a, 'k, W> {
+    fn scalar<F>(self, write: F) -> Result<()>
+    where
+        F: FnOnce(&mut Serializer<W>) -> Result<()>,
+    {
+        self.ser.write_indent()?;
+        self.ser.write(self.key)?;
+        self.ser.write_assignment("")?;
+        write(self.ser)?;
+        self.ser.write(";\n")
+    }
+
+    fn array(self) -> Result<ArraySerializer<'a, W>> {
+        self.ser.write_indent()?;
+        self.ser.write(self.key)?;
+        self.ser.write("[]")?;
+        let operator = self.ser.operator.take().unwrap_or("");
+        self.ser.write_assignment(operator)?;
+        Ok(ArraySerializer::new(self.ser, true))
+    }
+}
+
+impl<'a, 'k, W: io::Write> ser::Serializer for EntrySerializer<'a, 'k, W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = ArraySerializer<'a, W>;
+    type SerializeTuple = ArraySerializer<'a, W>;
+    type SerializeTupleStruct = ArraySerializer<'a, W>;
+    type SerializeTupleVariant = ArraySerializer<'a, W>;
+    type SerializeMap = ClassSerializer<'a, W>;
+    type SerializeStruct = ClassSerializer<'a, W>;
+    type SerializeStructVariant = ClassSerializer<'a, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.scalar(|s| s.write(if v { "1" } else { "0" }))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.scalar(|s| s.write(&v.to_string()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.scalar(|s| s.write(&v.to_string()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.scalar(|s| s.write_float(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.scalar(|s| s.write_float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(&v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.scalar(|s| s.write_string(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        use serde::ser::SerializeSeq;
+        let mut seq = self.serialize_seq(Some(v.len()))?;
+        for byte in v {
+            seq.serialize_element(byte)?;
+        }
+        seq.end()
+    }
+
+    // Arma has no null, missing values are left out.
+    fn serialize_none(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.ser.open_class(self.key, None)?;
+        self.ser.close_class()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        if name == DECLARATION_TOKEN {
+            self.ser.write_indent()?;
+            self.ser.write(if variant == "Delete" {
+                "delete "
+            } else {
+                "class "
+            })?;
+            self.ser.write(self.key)?;
+            return self.ser.write(";\n");
+        }
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if name == NUMBER_TOKEN {
+            let text = value.serialize(KeySerializer)?;
+            return self.scalar(|s| s.write(&text));
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if name == OPERATOR_TOKEN {
+            self.ser.operator = Some(if variant == "Remove" { "-" } else { "+" });
+            return value.serialize(self);
+        }
+        self.ser.open_class(self.key, None)?;
+        value.serialize(EntrySerializer {
+            ser: &mut *self.ser,
+            key: variant,
+        })?;
+        self.ser.close_class()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.array()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        self.array()
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.array()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        self.ser.open_class(self.key, None)?;
+        let mut array = EntrySerializer {
+            ser: self.ser,
+            key: variant,
+        }
+        .array()?;
+        array.variant = true;
+        Ok(array)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(ClassSerializer::new(self.ser, Some(self.key)))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(None)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        self.ser.open_class(self.key, None)?;
+        Ok(ClassSerializer::new(self.ser, Some(variant)))
+    }
+}
+
+// Collects the elements of an array, the layout is decided once the number
+// of elements is known. Nested arrays are always written inline.
+pub struct ArraySerializer<'a, W> {
+    ser: &'a mut Serializer<W>,
+    elements: Vec<String>,
+    entry: bool,
+    variant: bool,
+}
+
+impl<'a, W: io::Write> ArraySerializer<'a, W> {
+    fn new(ser: &'a mut Serializer<W>, entry: bool) -> Self {
+        ArraySerializer {
+            ser,
+            elements: Vec::new(),
+            entry,
+            variant: false,
+        }
+    }
+
+    fn element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut element = Serializer::with_config(Vec::new(), self.ser.config.clone());
+        value.serialize(ValueSerializer { ser: &mut element })?;
+        // The serializer only ever writes valid UTF-8.
+        self.elements.push(
+            String::from_utf8(element.into_inner()).expect("serializer produced invalid UTF-8"),
+        );
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        let multiline = self.entry
+            && self
+                .ser
+                .config
+                .array_inline_limit
+                .is_some_and(|limit| self.elements.len() > limit);
+        if multiline {
+            if self.ser.config.brace_on_new_line {
+                self.ser.write("\n")?;
+                self.ser.write_indent()?;
+            }
+            self.ser.write("{\n")?;
+            self.ser.depth += 1;
+            let last = self.elements.len() - 1;
+            for (i, element) in self.elements.iter().enumerate() {
+                self.ser.write_indent()?;
+                self.ser.write(element)?;
+                self.ser.write(if i == last { "\n" } else { ",\n" })?;
+            }
+            self.ser.depth -= 1;
+            self.ser.write_indent()?;
+            self.ser.write("}")?;
+        } else {
+            let separator = if self.ser.config.space_after_comma {
+                ", "
+            } else {
+                ","
+            };
+            self.ser.write("{")?;
+            self.ser.write(&self.elements.join(separator))?;
+            self.ser.write("}")?;
+        }
+        if self.entry {
+            self.ser.write(";\n")?;
+        }
+        if self.variant {
+            self.ser.close_class()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeSeq for ArraySerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeTuple for ArraySerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeTupleStruct for ArraySerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<'a, W: io::Write> ser::SerializeTupleVariant for ArraySerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+// Serializes an array element. Arrays can hold strings, numbers and other
+// arrays, but not classes.
+struct ValueSerializer<'a, W> {
+    ser: &'a mut Serializer<W>,
+}
+
+impl<'a, W: io::Write> ser::Serializer for ValueSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = ArraySerializer<'a, W>;
+    type SerializeTuple = ArraySerializer<'a, W>;
+    type SerializeTupleStruct = ArraySerializer<'a, W>;
+    type SerializeTupleVariant = ser::Impossible<(), Error>;
+    type SerializeMap = ser::Impossible<(), Error>;
+    type SerializeStruct = ser::Impossible<(), Error>;
+    type SerializeStructVariant = ser::Impossible<(), Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.ser.write(if v { "1" } else { "0" })
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.ser.write(&v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.ser.write(&v.to_string())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.ser.write_float(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.ser.write_float(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(&v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.ser.write_string(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        use serde::ser::SerializeSeq;
+        let mut seq = self.serialize_seq(Some(v.len()))?;
+        for byte in v {
+            seq.serialize_element(byte)?;
+        }
+        seq.end()
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Err(ErrorKind::Unsupported("missing value inside an array").into())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Err(ErrorKind::Unsupported("missing value inside an array").into())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Err(ErrorKind::Unsupported("class inside an array").into())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        if name == DECLARATION_TOKEN {
+            return Err(ErrorKind::Unsupported("class declaration inside an array").into());
+        }
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if name == NUMBER_TOKEN {
+            let text = value.serialize(KeySerializer)?;
+            return self.ser.write(&text);
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(ErrorKind::Unsupported("class inside an array").into())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(ArraySerializer::new(self.ser, false))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(ErrorKind::Unsupported("class inside an array").into())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(ErrorKind::Unsupported("class inside an array").into())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(ErrorKind::Unsupported("class inside an array").into())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(ErrorKind::Unsupported("class inside an array").into())
+    }
+}
+
+// Class names and property names are bare identifiers.
+struct KeySerializer;
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = ser::Impossible<String, Error>;
+    type SerializeTuple = ser::Impossible<String, Error>;
+    type SerializeTupleStruct = ser::Impossible<String, Error>;
+    type SerializeTupleVariant = ser::Impossible<String, Error>;
+    type SerializeMap = ser::Impossible<String, Error>;
+    type SerializeStruct = ser::Impossible<String, Error>;
+    type SerializeStructVariant = ser::Impossible<String, Error>;
+
+    fn serialize_bool(self, _v: bool) -> Result<String> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    // Only valid for the parent entry, where it means there is no parent.
+    fn serialize_none(self) -> Result<String> {
+        Ok(String::new())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(ErrorKind::KeyMustBeAString.into())
+    }
+}
@@ -0,0 +1,108 @@
+use crate::error::{Error, Position};
+
+/// Where each part of a preprocessed config comes from, so positions in it
+/// can be traced back to the files that were included and the macros that
+/// were expanded.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug)]
+struct SourceFile {
+    name: String,
+    text: String,
+}
+
+// The output from `output` up to the next segment. Text copied from a file
+// maps byte for byte from `offset` on, the text of a macro expansion maps to
+// the invocation at `offset`.
+#[derive(Clone, Debug)]
+struct Segment {
+    output: usize,
+    file: usize,
+    offset: usize,
+    macros: Vec<String>,
+}
+
+/// A place in one of the files that were preprocessed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location<'a> {
+    pub file: &'a str,
+    pub position: Position,
+    /// The macros that produced the text, outermost first. The position is
+    /// then where the outermost one is used.
+    pub macros: &'a [String],
+}
+
+impl SourceMap {
+    pub(crate) fn add_file(&mut self, name: &str, text: &str) -> usize {
+        self.files.push(SourceFile {
+            name: name.to_string(),
+            text: text.to_string(),
+        });
+        self.files.len() - 1
+    }
+
+    pub(crate) fn file_name(&self, file: usize) -> &str {
+        &self.files[file].name
+    }
+
+    pub(crate) fn add_segment(
+        &mut self,
+        output: usize,
+        file: usize,
+        offset: usize,
+        macros: Vec<String>,
+    ) {
+        if self.segments.last().is_some_and(|s| s.output == output) {
+            self.segments.pop();
+        }
+        self.segments.push(Segment {
+            output,
+            file,
+            offset,
+            macros,
+        });
+    }
+
+    /// Where the byte at `offset` in the preprocessed text comes from.
+    pub fn locate(&self, offset: usize) -> Option<Location<'_>> {
+        let index = self.segments.partition_point(|s| s.output <= offset);
+        let segment = &self.segments[index.checked_sub(1)?];
+        let file = &self.files[segment.file];
+        let mut source = segment.offset;
+        if segment.macros.is_empty() {
+            source = (source + offset - segment.output).min(file.text.len());
+        }
+        while !file.text.is_char_boundary(source) {
+            source -= 1;
+        }
+        Some(Location {
+            file: &file.name,
+            position: Position::from_offset(&file.text, source),
+            macros: &segment.macros,
+        })
+    }
+
+    /// The text of a file that was read, as it was before preprocessing.
+    pub fn source(&self, file: &str) -> Option<&str> {
+        self.files
+            .iter()
+            .find(|f| f.name == file)
+            .map(|f| f.text.as_str())
+    }
+
+    /// Moves the position of an error in the preprocessed text to the file it
+    /// comes from. Errors that already name a file are left alone.
+    pub fn translate(&self, err: Error) -> Error {
+        if err.file().is_some() {
+            return err;
+        }
+        match err.position().and_then(|p| self.locate(p.offset)) {
+            Some(location) => err.relocate(location),
+            None => err,
+        }
+    }
+}
@@ -0,0 +1,441 @@
+use std::fmt::{self, Display};
+
+use crate::error::{Error, ErrorKind, Position, Result};
+
+/// A config as written, before preprocessing, that can be edited and written
+/// back. Everything the tree does not edit is kept byte for byte, so writing
+/// an unchanged document gives back the text it was parsed from.
+///
+/// Directives like `#define` and `#include` are kept as `Node::Directive`,
+/// and entries that only make sense after macros are expanded, like
+/// `PREP(init);` or `class DOUBLES(a,b) {...};`, as `Node::Opaque`. Values
+/// are kept as the text they were written as, so `name = QUOTE(ADDON);` is a
+/// property whose value is `QUOTE(ADDON)`. A conditional must hold whole
+/// entries, one that opens a class in both branches can't be parsed.
+///
+/// ```
+/// use serde_arma::Document;
+///
+/// let text = "#include \"macros.hpp\"\nclass CfgPatches {\n    class ADDON {\n        version = 1; // bumped by CI\n    };\n};\n";
+/// let mut document = Document::parse(text).unwrap();
+/// let patch = document.class_mut("CfgPatches").and_then(|c| c.class_mut("ADDON")).unwrap();
+/// patch.property_mut("version").unwrap().set_value("2");
+/// assert_eq!(text.replace("version = 1", "version = 2"), document.to_string());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Document {
+    nodes: Vec<Node>,
+}
+
+/// A piece of a `Document`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    /// Whitespace and comments.
+    Trivia(String),
+    /// A preprocessor directive with its continuation lines, without the
+    /// line break that ends it.
+    Directive(String),
+    /// Anything else up to its `;`, or the end of the line: macro
+    /// invocations, `__EXEC`, external declarations `class Name;` and
+    /// `delete Name;`.
+    Opaque(String),
+    Property(PropertyNode),
+    Class(ClassNode),
+}
+
+/// `name = value;`, `name[] = {...};` or `name[] += {...};`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyNode {
+    name: String,
+    // From the name up to the value, like `name[] = `.
+    head: String,
+    value: String,
+    // From the value up to and including the `;`.
+    tail: String,
+}
+
+/// `class Name: Parent {...};`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassNode {
+    name: String,
+    parent: Option<String>,
+    // Up to and including the `{`.
+    head: String,
+    nodes: Vec<Node>,
+    // From the `}` up to and including the `;`.
+    tail: String,
+}
+
+impl Document {
+    pub fn parse(text: &str) -> Result<Document> {
+        let mut parser = Parser { text, pos: 0 };
+        let nodes = parser.nodes(true)?;
+        Ok(Document { nodes })
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn nodes_mut(&mut self) -> &mut Vec<Node> {
+        &mut self.nodes
+    }
+
+    /// The first property named `name`, ignoring case like the game.
+    pub fn property_mut(&mut self, name: &str) -> Option<&mut PropertyNode> {
+        property_mut(&mut self.nodes, name)
+    }
+
+    /// The first class named `name`, ignoring case like the game.
+    pub fn class_mut(&mut self, name: &str) -> Option<&mut ClassNode> {
+        class_mut(&mut self.nodes, name)
+    }
+
+    /// Parses `text` and adds its nodes at the end.
+    pub fn push(&mut self, text: &str) -> Result<()> {
+        let nodes = Document::parse(text)?.nodes;
+        self.nodes.extend(nodes);
+        Ok(())
+    }
+}
+
+impl PropertyNode {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The value as it is written, without the `;`.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Replaces the text of the value, leaving the rest of the entry as it
+    /// is. `value` is written out as it is given, strings need their quotes.
+    pub fn set_value(&mut self, value: &str) {
+        self.value = value.to_string();
+    }
+}
+
+impl ClassNode {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn nodes_mut(&mut self) -> &mut Vec<Node> {
+        &mut self.nodes
+    }
+
+    /// The first property named `name`, ignoring case like the game.
+    pub fn property_mut(&mut self, name: &str) -> Option<&mut PropertyNode> {
+        property_mut(&mut self.nodes, name)
+    }
+
+    /// The first class named `name`, ignoring case like the game.
+    pub fn class_mut(&mut self, name: &str) -> Option<&mut ClassNode> {
+        class_mut(&mut self.nodes, name)
+    }
+
+    /// Parses `text` and adds its nodes after the last entry, before the
+    /// line break and indentation of the closing `}`. Start `text` with the
+    /// line break and indentation the new entries should have.
+    pub fn push(&mut self, text: &str) -> Result<()> {
+        let nodes = Document::parse(text)?.nodes;
+        let end = match self.nodes.last() {
+            Some(Node::Trivia(_)) => self.nodes.len() - 1,
+            _ => self.nodes.len(),
+        };
+        self.nodes.splice(end..end, nodes);
+        Ok(())
+    }
+}
+
+fn property_mut<'a>(nodes: &'a mut [Node], name: &str) -> Option<&'a mut PropertyNode> {
+    nodes.iter_mut().find_map(|node| match node {
+        Node::Property(p) if p.name.eq_ignore_ascii_case(name) => Some(p),
+        _ => None,
+    })
+}
+
+fn class_mut<'a>(nodes: &'a mut [Node], name: &str) -> Option<&'a mut ClassNode> {
+    nodes.iter_mut().find_map(|node| match node {
+        Node::Class(c) if c.name.eq_ignore_ascii_case(name) => Some(c),
+        _ => None,
+    })
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error::new(kind, Some(Position::from_offset(self.text, self.pos)))
+    }
+
+    // The entries of the root, or of a class up to its `}`.
+    fn nodes(&mut self, root: bool) -> Result<Vec<Node>> {
+        let mut nodes = Vec::new();
+        loop {
+            let start = self.pos;
+            self.skip_trivia()?;
+            if self.pos > start {
+                nodes.push(Node::Trivia(self.text[start..self.pos].to_string()));
+            }
+            let rest = self.rest();
+            if rest.is_empty() {
+                if root {
+                    return Ok(nodes);
+                }
+                return Err(self.error(ErrorKind::UnexpectedEof));
+            }
+            if rest.starts_with('}') {
+                if root {
+                    return Err(self.error(ErrorKind::TrailingCharacters));
+                }
+                return Ok(nodes);
+            }
+            let node = if rest.starts_with('#') {
+                self.directive()
+            } else if let Some(class) = self.class()? {
+                Node::Class(class)
+            } else if let Some(property) = self.property()? {
+                Node::Property(property)
+            } else {
+                Node::Opaque(self.opaque()?.to_string())
+            };
+            nodes.push(node);
+        }
+    }
+
+    // Whitespace and comments, up to a directive at the start of a line.
+    fn skip_trivia(&mut self) -> Result<()> {
+        loop {
+            let rest = self.rest();
+            if rest.starts_with(|c: char| c.is_whitespace()) {
+                let len = rest
+                    .find(|c: char| !c.is_whitespace())
+                    .unwrap_or(rest.len());
+                self.pos += len;
+            } else if rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                match comment.find("*/") {
+                    Some(end) => self.pos += end + 4,
+                    None => {
+                        self.pos = self.text.len();
+                        return Err(self.error(ErrorKind::UnexpectedEof));
+                    }
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // Up to the end of the line, and the next ones while they end in `\`.
+    fn directive(&mut self) -> Node {
+        let start = self.pos;
+        loop {
+            let rest = self.rest();
+            let end = rest.find('\n').unwrap_or(rest.len());
+            let continued = rest[..end].trim_end_matches('\r').ends_with('\\');
+            self.pos += end;
+            if !continued || end == rest.len() {
+                return Node::Directive(self.text[start..self.pos].to_string());
+            }
+            self.pos += 1;
+        }
+    }
+
+    // `class Name: Parent {`, or `None` without moving if the entry is
+    // something else, like an external declaration.
+    fn class(&mut self) -> Result<Option<ClassNode>> {
+        let start = self.pos;
+        let rest = self.rest();
+        if !rest.starts_with("class") || !rest[5..].starts_with(char::is_whitespace) {
+            return Ok(None);
+        }
+        self.pos += 5;
+        self.skip_trivia()?;
+        let name = self.word();
+        self.skip_trivia()?;
+        let mut parent = None;
+        if self.rest().starts_with(':') {
+            self.pos += 1;
+            self.skip_trivia()?;
+            parent = Some(self.word().to_string());
+            self.skip_trivia()?;
+        }
+        if name.is_empty() || parent.as_deref() == Some("") || !self.rest().starts_with('{') {
+            self.pos = start;
+            return Ok(None);
+        }
+        self.pos += 1;
+        let head = self.text[start..self.pos].to_string();
+        let nodes = self.nodes(false)?;
+        let tail_start = self.pos;
+        self.pos += 1;
+        let rest = self.rest();
+        let ws = rest.len() - rest.trim_start().len();
+        if rest[ws..].starts_with(';') {
+            self.pos += ws + 1;
+        }
+        Ok(Some(ClassNode {
+            name: name.to_string(),
+            parent,
+            head,
+            nodes,
+            tail: self.text[tail_start..self.pos].to_string(),
+        }))
+    }
+
+    // `name = value;`, or `None` without moving if the entry is something
+    // else, like a macro invocation.
+    fn property(&mut self) -> Result<Option<PropertyNode>> {
+        let start = self.pos;
+        let name = self.word();
+        self.skip_trivia()?;
+        if self.rest().starts_with('[') {
+            self.pos += 1;
+            self.skip_trivia()?;
+            if self.rest().starts_with(']') {
+                self.pos += 1;
+                self.skip_trivia()?;
+            }
+        }
+        let rest = self.rest();
+        let operator = ["=", "+=", "-="].iter().find(|op| rest.starts_with(**op));
+        let operator = match operator {
+            Some(op) if !name.is_empty() => op,
+            _ => {
+                self.pos = start;
+                return Ok(None);
+            }
+        };
+        self.pos += operator.len();
+        self.skip_trivia()?;
+        let head = self.text[start..self.pos].to_string();
+        let value_start = self.pos;
+        let value = self.scan(false)?;
+        let value = value.trim_end();
+        self.pos = value_start + value.len();
+        let tail_start = self.pos;
+        let rest = self.rest();
+        let ws = rest.len() - rest.trim_start().len();
+        if rest[ws..].starts_with(';') {
+            self.pos += ws + 1;
+        }
+        Ok(Some(PropertyNode {
+            name: name.to_string(),
+            head,
+            value: value.to_string(),
+            tail: self.text[tail_start..self.pos].to_string(),
+        }))
+    }
+
+    // Up to and including the `;` that ends the entry, or up to the end of
+    // the line or the `}` of the class.
+    fn opaque(&mut self) -> Result<&'a str> {
+        let start = self.pos;
+        self.scan(true)?;
+        if self.rest().starts_with(';') {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            // A stray character like `)`, taken on its own.
+            self.pos += self.rest().chars().next().map_or(0, char::len_utf8);
+        }
+        Ok(&self.text[start..self.pos])
+    }
+
+    // Moves to the next `;` or `}` outside of braces, parentheses, strings
+    // and comments, or the next line break too when `line` is set. Returns
+    // what it passed over.
+    fn scan(&mut self, line: bool) -> Result<&'a str> {
+        let start = self.pos;
+        let mut depth = 0;
+        while let Some(c) = self.rest().chars().next() {
+            match c {
+                ';' | '}' if depth == 0 => break,
+                '\n' if line && depth == 0 => break,
+                '{' | '(' | '[' => depth += 1,
+                '}' | ')' | ']' if depth > 0 => depth -= 1,
+                '"' => {
+                    let len = self.rest()[1..].find('"').ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::UnexpectedEof,
+                            Some(Position::from_offset(self.text, self.text.len())),
+                        )
+                    })?;
+                    self.pos += len + 2;
+                    continue;
+                }
+                '/' if self.rest().starts_with("//") || self.rest().starts_with("/*") => {
+                    if line && self.rest().starts_with("//") {
+                        break;
+                    }
+                    self.skip_trivia()?;
+                    continue;
+                }
+                _ => {}
+            }
+            self.pos += c.len_utf8();
+        }
+        Ok(&self.text[start..self.pos])
+    }
+
+    // A class or property name.
+    fn word(&mut self) -> &'a str {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+}
+
+impl Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.nodes.iter().try_for_each(|node| node.fmt(f))
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Node::Trivia(ref text) | Node::Directive(ref text) | Node::Opaque(ref text) => {
+                f.write_str(text)
+            }
+            Node::Property(ref property) => property.fmt(f),
+            Node::Class(ref class) => class.fmt(f),
+        }
+    }
+}
+
+impl Display for PropertyNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}{}", self.head, self.value, self.tail)
+    }
+}
+
+impl Display for ClassNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.head)?;
+        self.nodes.iter().try_for_each(|node| node.fmt(f))?;
+        f.write_str(&self.tail)
+    }
+}
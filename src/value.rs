@@ -0,0 +1,717 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{
+    self, Deserialize, DeserializeOwned, Deserializer, EnumAccess, IntoDeserializer, MapAccess,
+    SeqAccess, VariantAccess, Visitor,
+};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::class::{
+    Declaration, DeclarationDeserializer, Operator, DECLARATION_TOKEN, NUMBER_TOKEN,
+    OPERATOR_TOKEN, PARENT_KEY, VALUE_TOKEN,
+};
+use crate::error::{Error, ErrorKind};
+use crate::number::{self, Literal};
+
+/// Any value that can appear in a config.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Number(Number),
+    Array(Vec<Value>),
+    Class(Class),
+    /// An external class declaration, `class Name;`.
+    External,
+    /// `delete Name;`
+    Delete,
+    /// An array added to the inherited one, `name[] += {...};`.
+    Append(Vec<Value>),
+    /// An array removed from the inherited one, `name[] -= {...};`.
+    Remove(Vec<Value>),
+}
+
+/// A number and the text it was read from. A number read from a config is
+/// written back as that text, like `0x1F` or `1e-006`, in any format. Other
+/// numbers are written as integers or floats, whichever they were made from.
+///
+/// Two numbers are equal when their values are, however they were written.
+#[derive(Clone, Debug)]
+pub struct Number {
+    n: N,
+    text: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum N {
+    Int(i64),
+    Float(f64),
+}
+
+/// The entries of a class in the order they were defined, and the name of the
+/// class it inherits from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Class {
+    pub(crate) parent: Option<String>,
+    pub(crate) entries: Vec<(String, Value)>,
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Number(ref n) => n.as_i64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Number(ref n) => Some(n.as_f64()),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match *self {
+            Value::Array(ref a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_class(&self) -> Option<&Class> {
+        match *self {
+            Value::Class(ref c) => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn as_class_mut(&mut self) -> Option<&mut Class> {
+        match *self {
+            Value::Class(ref mut c) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+impl Number {
+    /// The value if the number is an integer.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.n {
+            N::Int(n) => Some(n),
+            N::Float(_) => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self.n {
+            N::Int(n) => n as f64,
+            N::Float(n) => n,
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self.n, N::Float(_))
+    }
+
+    /// The number as it was written in the config, like `0x1F` or `1e-006`,
+    /// if it was read from one.
+    pub fn as_str(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+}
+
+impl From<i64> for Number {
+    fn from(n: i64) -> Self {
+        Number {
+            n: N::Int(n),
+            text: None,
+        }
+    }
+}
+
+impl From<f64> for Number {
+    fn from(n: f64) -> Self {
+        Number {
+            n: N::Float(n),
+            text: None,
+        }
+    }
+}
+
+impl FromStr for Number {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let (literal, len) = number::lex(s, "")?;
+        if len != s.len() {
+            return Err(ErrorKind::InvalidNumber(s.to_string()).into());
+        }
+        let n = match literal {
+            Literal::Signed(n) => N::Int(n),
+            Literal::Unsigned(n) if n <= i64::MAX as u64 => N::Int(n as i64),
+            literal => N::Float(literal.as_f64()),
+        };
+        Ok(Number {
+            n,
+            text: Some(s.to_string()),
+        })
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Number) -> bool {
+        self.n == other.n
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.text, self.n) {
+            (Some(text), _) => formatter.write_str(text),
+            (None, N::Int(n)) => write!(formatter, "{}", n),
+            (None, N::Float(n)) => write!(formatter, "{}", n),
+        }
+    }
+}
+
+impl Class {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+
+    pub fn set_parent(&mut self, parent: Option<String>) {
+        self.parent = parent;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.entries
+            .iter_mut()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Replaces the value of an existing entry in place, or appends a new
+    /// entry. Returns the previous value.
+    pub fn insert<K: Into<String>>(&mut self, key: K, value: Value) -> Option<Value> {
+        let key = key.into();
+        match self.get_mut(&key) {
+            Some(existing) => Some(std::mem::replace(existing, value)),
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let pos = self.entries.iter().position(|(k, _)| k == key)?;
+        Some(self.entries.remove(pos).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(s: &'a str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Number(Number::from(n))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(Number::from(n))
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(a: Vec<Value>) -> Self {
+        Value::Array(a)
+    }
+}
+
+impl From<Class> for Value {
+    fn from(c: Class) -> Self {
+        Value::Class(c)
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Value::String(ref s) => serializer.serialize_str(s),
+            Value::Number(ref n) => n.serialize(serializer),
+            Value::Array(ref a) => {
+                let mut seq = serializer.serialize_seq(Some(a.len()))?;
+                for element in a {
+                    seq.serialize_element(element)?;
+                }
+                seq.end()
+            }
+            Value::Class(ref c) => c.serialize(serializer),
+            Value::External => serializer.serialize_unit_variant(
+                DECLARATION_TOKEN,
+                0,
+                Declaration::External.variant(),
+            ),
+            Value::Delete => serializer.serialize_unit_variant(
+                DECLARATION_TOKEN,
+                1,
+                Declaration::Delete.variant(),
+            ),
+            Value::Append(ref a) => serializer.serialize_newtype_variant(
+                OPERATOR_TOKEN,
+                0,
+                Operator::Append.variant(),
+                a,
+            ),
+            Value::Remove(ref a) => serializer.serialize_newtype_variant(
+                OPERATOR_TOKEN,
+                1,
+                Operator::Remove.variant(),
+                a,
+            ),
+        }
+    }
+}
+
+impl Serialize for Number {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.text {
+            Some(ref text) => serializer.serialize_newtype_struct(NUMBER_TOKEN, text),
+            None => self.n.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for N {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            N::Int(n) => serializer.serialize_i64(n),
+            N::Float(n) => serializer.serialize_f64(n),
+        }
+    }
+}
+
+impl Serialize for Class {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        if let Some(ref parent) = self.parent {
+            map.serialize_entry(PARENT_KEY, parent)?;
+        }
+        for (key, value) in &self.entries {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_newtype_struct(VALUE_TOKEN, ValueVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Class {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Class, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Class(c) => Ok(c),
+            _ => Err(de::Error::custom("expected a class")),
+        }
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a config value")
+    }
+
+    // Arma has no booleans, they are stored as 0 and 1.
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::from(v as i64))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        if v <= i64::MAX as u64 {
+            Ok(Value::from(v as i64))
+        } else {
+            Ok(Value::from(v as f64))
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Value, A::Error> {
+        let (variant, access): (String, _) = data.variant()?;
+        match variant.as_str() {
+            NUMBER_TOKEN => {
+                let text: String = access.newtype_variant()?;
+                text.parse().map(Value::Number).map_err(de::Error::custom)
+            }
+            "External" => access.unit_variant().map(|_| Value::External),
+            "Delete" => access.unit_variant().map(|_| Value::Delete),
+            "Append" => access.newtype_variant().map(Value::Append),
+            "Remove" => access.newtype_variant().map(Value::Remove),
+            _ => Err(de::Error::unknown_variant(
+                &variant,
+                &["External", "Delete", "Append", "Remove"],
+            )),
+        }
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut array = Vec::new();
+        while let Some(element) = seq.next_element()? {
+            array.push(element);
+        }
+        Ok(Value::Array(array))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut class = Class::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if key == PARENT_KEY {
+                class.parent = Some(map.next_value()?);
+            } else {
+                let value = map.next_value()?;
+                class.entries.push((key, value));
+            }
+        }
+        Ok(Value::Class(class))
+    }
+}
+
+/// Interprets a `Value` as an instance of type `T`, for example after
+/// inheritance was resolved with `Value::resolve`.
+pub fn from_value<T>(value: Value) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(value)
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::String(s) => visitor.visit_string(s),
+            Value::Number(Number { n: N::Int(n), .. }) => visitor.visit_i64(n),
+            Value::Number(Number { n: N::Float(n), .. }) => visitor.visit_f64(n),
+            Value::Array(a) => {
+                let mut seq = SeqDeserializer::new(a.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Class(c) => {
+                let parent = c
+                    .parent
+                    .map(|parent| (PARENT_KEY.to_string(), Value::String(parent)));
+                let mut map = MapDeserializer::new(parent.into_iter().chain(c.entries));
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            Value::External => {
+                DeclarationDeserializer(Declaration::External).deserialize_any(visitor)
+            }
+            Value::Delete => DeclarationDeserializer(Declaration::Delete).deserialize_any(visitor),
+            Value::Append(a) => visitor.visit_enum(OperatorDeserializer(Operator::Append, a)),
+            Value::Remove(a) => visitor.visit_enum(OperatorDeserializer(Operator::Remove, a)),
+        }
+    }
+
+    // Typed arrays see the elements of `+=` and `-=` assignments. A class is
+    // a sequence of enums named after its entries.
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Append(a) | Value::Remove(a) => Value::Array(a).deserialize_any(visitor),
+            Value::Class(c) => {
+                let entries = c.entries.into_iter().map(|(k, v)| VariantEntry(k, v));
+                let mut seq = SeqDeserializer::new(entries);
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    // Maps don't see the parent, only structs with a `$parent` field do.
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Class(mut c) => {
+                c.parent = None;
+                Value::Class(c).deserialize_any(visitor)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        if fields.contains(&PARENT_KEY) {
+            return self.deserialize_any(visitor);
+        }
+        self.deserialize_map(visitor)
+    }
+
+    // Booleans are written as `0` and `1`.
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Number(Number { n: N::Int(0), .. }) => visitor.visit_bool(false),
+            Value::Number(Number { n: N::Int(1), .. }) => visitor.visit_bool(true),
+            _ => Err(ErrorKind::ExpectedBoolean.into()),
+        }
+    }
+
+    // Missing entries and declarations are the only things that map to `None`.
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::External | Value::Delete => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // A unit variant is a string, other variants are a class holding one
+    // entry named after the variant.
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::String(s) => visitor.visit_enum(s.into_deserializer()),
+            Value::Class(mut c) if c.entries.len() == 1 => {
+                let (name, value) = c.entries.remove(0);
+                visitor.visit_enum(VariantEntry(name, value))
+            }
+            _ => Err(ErrorKind::ExpectedEnum.into()),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple
+        tuple_struct identifier ignored_any
+    }
+}
+
+// An entry of a class read as an enum, its name picking the variant.
+struct VariantEntry(String, Value);
+
+impl<'de> IntoDeserializer<'de, Error> for VariantEntry {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for VariantEntry {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(ErrorKind::ExpectedEnum.into())
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> EnumAccess<'de> for VariantEntry {
+    type Error = Error;
+    type Variant = Value;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Value), Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(self.0))?;
+        Ok((variant, self.1))
+    }
+}
+
+struct OperatorDeserializer(Operator, Vec<Value>);
+
+impl<'de> EnumAccess<'de> for OperatorDeserializer {
+    type Error = Error;
+    type Variant = Value;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Value), Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(
+            self.0.variant(),
+        ))?;
+        Ok((variant, Value::Array(self.1)))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Value {
+    type Error = Error;
+
+    // `class Variant {};`
+    fn unit_variant(self) -> Result<(), Error> {
+        match self {
+            Value::Class(ref c) if c.entries.is_empty() => Ok(()),
+            _ => Err(ErrorKind::ExpectedMap.into()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+}
@@ -1,6 +1,5 @@
 use serde::Deserialize;
 
-use serde_arma;
 mod mission;
 
 #[test]
@@ -174,15 +173,16 @@ fn test_dumb_newline() {
 #[test]
 fn test_transcode() {
     use serde_json::{Serializer};
-    use std::io::{Write, BufReader, BufWriter};
-    use std::fs::File;
+    use std::io::{Write, BufWriter};
+    use std::fs::{self, File};
 
-    let reader = BufReader::new(File::open("tests/example.hpp").unwrap());
+    let text = fs::read_to_string("tests/example.hpp").unwrap();
     let writer = BufWriter::new(File::create("tests/example.json").unwrap());
 
-    let mut deserializer = serde_arma::from_reader(reader);
+    let mut deserializer = serde_arma::Deserializer::from_str(&text);
     let mut serializer = Serializer::pretty(writer);
     serde_transcode::transcode(&mut deserializer, &mut serializer).unwrap();
+    deserializer.end().unwrap();
     serializer.into_inner().flush().unwrap();
 }
 
@@ -194,3 +194,841 @@ fn test_mission() {
 
     let _: crate::mission::InternalArmaMission = serde_arma::from_str(&contents).unwrap();
 }
+
+#[test]
+fn test_inheritance() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        #[serde(rename = "Base")]
+        base: Vehicle,
+        #[serde(rename = "Car")]
+        car: Vehicle,
+        #[serde(rename = "Truck")]
+        truck: Vehicle,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Vehicle {
+        #[serde(rename = "$parent")]
+        parent: Option<String>,
+        speed: Option<u32>,
+    }
+
+    let j = r#"class Base { speed = 10; };
+class Car: Base { speed = 20; };
+class Truck : Car {};
+"#;
+    let expected = Test {
+        base: Vehicle {
+            parent: None,
+            speed: Some(10),
+        },
+        car: Vehicle {
+            parent: Some("Base".to_string()),
+            speed: Some(20),
+        },
+        truck: Vehicle {
+            parent: Some("Car".to_string()),
+            speed: None,
+        },
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+
+    let value: serde_arma::Value = serde_arma::from_str(j).unwrap();
+    assert_eq!(expected, serde_arma::from_value(value.clone()).unwrap());
+
+    // Maps and structs without a `$parent` field don't see the parent.
+    type Map = std::collections::HashMap<String, std::collections::HashMap<String, u32>>;
+    let map: Map = serde_arma::from_str(j).unwrap();
+    assert_eq!(Some(&20), map["Car"].get("speed"));
+    assert_eq!(map, serde_arma::from_value(value.clone()).unwrap());
+    #[derive(Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    struct Strict {
+        speed: u32,
+    }
+    let strict: std::collections::BTreeMap<String, Strict> =
+        serde_arma::from_str("class Car: Base { speed = 20; };").unwrap();
+    assert_eq!(Strict { speed: 20 }, strict["Car"]);
+    let value = value.as_class().and_then(|c| c.get("Car")).cloned();
+    assert_eq!(
+        Strict { speed: 20 },
+        serde_arma::from_value(value.unwrap()).unwrap()
+    );
+
+    for j in ["class A: {};", "class A : ;", "class A:"].iter() {
+        let err = serde_arma::from_str::<serde_arma::Value>(j).unwrap_err();
+        assert_eq!(&serde_arma::ErrorKind::ExpectedParent, err.kind(), "{}", j);
+    }
+}
+
+#[test]
+fn test_declarations() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        #[serde(rename = "CfgVehicles")]
+        vehicles: CfgVehicles,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct CfgVehicles {
+        #[serde(rename = "Man")]
+        man: Option<Child>,
+        #[serde(rename = "Car")]
+        car: Option<Child>,
+        #[serde(rename = "MySoldier")]
+        my_soldier: Child,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Child {
+        #[serde(rename = "$parent")]
+        parent: Option<String>,
+    }
+
+    let j = r#"class CfgPatches;
+class CfgVehicles {
+    class Man;
+    delete Car;
+    class MySoldier: Man {};
+};
+"#;
+    let expected = Test {
+        vehicles: CfgVehicles {
+            man: None,
+            car: None,
+            my_soldier: Child {
+                parent: Some("Man".to_string()),
+            },
+        },
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum Declaration {
+        External,
+        Delete,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Surfaced {
+        #[serde(rename = "Man")]
+        man: Declaration,
+        #[serde(rename = "Car")]
+        car: Declaration,
+    }
+
+    let j = "class Man;\ndelete Car;\n";
+    let mut deserializer = serde_arma::Deserializer::from_str(j).surface_declarations(true);
+    let expected = Surfaced {
+        man: Declaration::External,
+        car: Declaration::Delete,
+    };
+    assert_eq!(expected, Surfaced::deserialize(&mut deserializer).unwrap());
+
+    // A class header is followed by `;` or the body.
+    let cases = [("class A x; s = 1;", 9), ("class A = 1;", 9)];
+    for (j, column) in cases.iter() {
+        let err = serde_arma::from_str::<serde_arma::Value>(j).unwrap_err();
+        assert_eq!(&serde_arma::ErrorKind::ExpectedMap, err.kind());
+        assert_eq!((Some(1), Some(*column)), (err.line(), err.column()));
+        let err = serde_arma::from_str::<std::collections::HashMap<String, String>>(j);
+        assert!(err.is_err(), "{}", j);
+    }
+}
+
+#[test]
+fn test_array_operators() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        items: Vec<String>,
+        removed: Vec<u8>,
+        after: String,
+    }
+
+    let j = r#"items[] += {"a", "b"};removed[]-={1};after = "hi";"#;
+    let expected = Test {
+        items: vec!["a".to_string(), "b".to_string()],
+        removed: vec![1],
+        after: "hi".to_string(),
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+}
+
+#[test]
+fn test_error_position() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        int: u32,
+        seq: Vec<String>,
+    }
+
+    let j = "int = 1;\nseq[] = {\"a\" \"b\"};";
+    let err = serde_arma::from_str::<Test>(j).unwrap_err();
+    assert_eq!(&serde_arma::ErrorKind::ExpectedArrayComma, err.kind());
+    assert_eq!(Some(2), err.line());
+    assert_eq!(Some(14), err.column());
+    assert_eq!(
+        "expected `,` between array elements in `seq` at line 2 column 14",
+        err.to_string()
+    );
+
+    let j = "int = 1\nseq[] = {};";
+    let err = serde_arma::from_str::<Test>(j).unwrap_err();
+    assert_eq!(&serde_arma::ErrorKind::ExpectedSemiColon, err.kind());
+    assert_eq!((Some(2), Some(1)), (err.line(), err.column()));
+
+    // Errors raised by the visitor point at the value it rejected.
+    let j = "int = 1;\n  seq[] = {\"a\", {}};";
+    let err = serde_arma::from_str::<Test>(j).unwrap_err();
+    assert_eq!((Some(2), Some(17)), (err.line(), err.column()));
+}
+
+#[test]
+fn test_error_path() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Mission {
+        #[serde(rename = "Entities")]
+        entities: Entities,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Entities {
+        #[serde(rename = "Item0")]
+        item0: Item,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Item {
+        side: String,
+        skill: Vec<u8>,
+    }
+
+    let j = r#"class Mission {
+    class Entities {
+        class Item0 {
+            side = "West";
+        };
+    };
+};"#;
+    let err = serde_arma::from_str::<std::collections::HashMap<String, Mission>>(j).unwrap_err();
+    assert_eq!(Some("Mission.Entities.Item0"), err.path());
+    assert_eq!(
+        &serde_arma::ErrorKind::Message("missing field `skill`".to_string()),
+        err.kind()
+    );
+
+    let j = r#"class Mission {
+    class Entities {
+        class Item0 {
+            side = "West";
+            skill = 5;
+        };
+    };
+};"#;
+    let err = serde_arma::from_str::<std::collections::HashMap<String, Mission>>(j).unwrap_err();
+    assert_eq!(Some("Mission.Entities.Item0.skill"), err.path());
+    assert_eq!((Some(5), Some(21)), (err.line(), err.column()));
+}
+
+#[test]
+fn test_malformed() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        int: u32,
+    }
+
+    let inputs = [
+        "int = 1x;",
+        "int = -;",
+        "int = \"unterminated",
+        "int = ",
+        "int",
+        "class int {",
+        "int[] = {1,",
+    ];
+    for input in inputs.iter() {
+        assert!(serde_arma::from_str::<Test>(input).is_err(), "{}", input);
+        assert!(
+            serde_arma::from_str::<serde_arma::Value>(input).is_err(),
+            "{}",
+            input
+        );
+    }
+    assert!(serde_arma::from_str::<Test>("int = 99999999999;").is_err());
+    assert!(serde_arma::from_str::<Test>("int = 1.5;").is_err());
+
+    let err = serde_arma::from_str::<Test>("int = 1x;").unwrap_err();
+    assert_eq!(
+        &serde_arma::ErrorKind::InvalidNumber("1x".to_string()),
+        err.kind()
+    );
+    assert_eq!(Some(7), err.column());
+    let err = serde_arma::from_str::<serde_arma::Value>("int = \"abc").unwrap_err();
+    assert_eq!(&serde_arma::ErrorKind::UnexpectedEof, err.kind());
+
+    // An unterminated class is missing its `};`, not the `;` of its last entry.
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Outer {
+        #[serde(rename = "A")]
+        a: Test,
+    }
+    let err = serde_arma::from_str::<Outer>("class A {\n    int = 1;\n").unwrap_err();
+    assert_eq!(&serde_arma::ErrorKind::ExpectedMapEnd, err.kind());
+    assert_eq!((Some(3), Some(1)), (err.line(), err.column()));
+    let err = serde_arma::from_str::<serde_arma::Value>("class A { x = 1;").unwrap_err();
+    assert_eq!(&serde_arma::ErrorKind::ExpectedMapEnd, err.kind());
+}
+
+#[test]
+fn test_char_enum() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum Side {
+        West,
+        East,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        letter: char,
+        side: Side,
+    }
+
+    let j = r#"letter = "a"; side = "East";"#;
+    let expected = Test {
+        letter: 'a',
+        side: Side::East,
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+
+    assert!(serde_arma::from_str::<Test>(r#"letter = "ab";"#).is_err());
+    let j = r#"letter = "a"; side = "North";"#;
+    assert!(serde_arma::from_str::<Test>(j).is_err());
+}
+
+#[test]
+fn test_borrowed_strings() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test<'a> {
+        plain: &'a str,
+        escaped: std::borrow::Cow<'a, str>,
+        joined: String,
+    }
+
+    let j = "plain = \"hello\";\nescaped = \"say \"\"hi\"\"\";\njoined = \"a\" \\n \"b\";";
+    let test: Test = serde_arma::from_str(j).unwrap();
+    assert_eq!("hello", test.plain);
+    assert_eq!("say \"hi\"", test.escaped);
+    assert_eq!("a\nb", test.joined);
+}
+
+#[test]
+fn test_from_reader_slice() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        int: u32,
+        seq: Vec<String>,
+    }
+
+    let j = "int = 1;\nseq[] = {\"a\", \"b\"};";
+    let expected = Test {
+        int: 1,
+        seq: vec!["a".to_string(), "b".to_string()],
+    };
+    assert_eq!(expected, serde_arma::from_reader(j.as_bytes()).unwrap());
+    assert_eq!(expected, serde_arma::from_slice(j.as_bytes()).unwrap());
+    assert_eq!(
+        &serde_arma::ErrorKind::TrailingCharacters,
+        serde_arma::from_reader::<_, Test>("int = 1;\nseq[] = {};\n};".as_bytes())
+            .unwrap_err()
+            .kind()
+    );
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Braces {
+        x: u32,
+        #[serde(rename = "A")]
+        a: Option<std::collections::HashMap<String, u32>>,
+    }
+    let cases = [
+        ("x = 1; }", 8),
+        ("x = 1; }}", 8),
+        ("class A { x = 1; }; }", 21),
+        ("x = 1;\nclass A { x = 1; };\n}", 1),
+    ];
+    for (j, column) in cases.iter() {
+        let err = serde_arma::from_str::<Braces>(j).unwrap_err();
+        assert_eq!(&serde_arma::ErrorKind::TrailingCharacters, err.kind(), "{}", j);
+        assert_eq!(Some(*column), err.column(), "{}", j);
+        let err = serde_arma::from_str::<serde_arma::Value>(j).unwrap_err();
+        assert_eq!(&serde_arma::ErrorKind::TrailingCharacters, err.kind(), "{}", j);
+    }
+}
+
+#[test]
+fn test_from_slice_invalid() {
+    let err = serde_arma::from_slice::<serde_arma::Value>(b"name = \"\xff\";").unwrap_err();
+    assert!(matches!(err.kind(), serde_arma::ErrorKind::Io(_)));
+
+    let err =
+        serde_arma::from_slice::<serde_arma::Value>(b"name = 1;\n};\nextra = 2;").unwrap_err();
+    assert_eq!(&serde_arma::ErrorKind::TrailingCharacters, err.kind());
+    assert_eq!(Some(2), err.line());
+}
+
+#[test]
+fn test_comments() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        numbers: Vec<u8>,
+        after: String,
+        child: Child,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Child {
+        number: u32,
+    }
+
+    let j = r#"// the numbers
+numbers[] /* array */ = { // first
+    1, /* second */ 2,
+    3 // last
+};
+after = "hi"; /* trailing */
+/* the
+   child */
+class child /* no parent */ {
+    number /* before = */ = 123 /* before ; */;
+    // number = 1;
+};
+// end"#;
+    let expected = Test {
+        numbers: vec![1, 2, 3],
+        after: "hi".to_string(),
+        child: Child { number: 123 },
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+
+    let err = serde_arma::from_str::<serde_arma::Value>("a = 1; /* open").unwrap_err();
+    assert_eq!(&serde_arma::ErrorKind::UnexpectedEof, err.kind());
+}
+
+#[test]
+fn test_number_literals() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        flags: u32,
+        negative_hex: i32,
+        plus: i8,
+        half: f32,
+        small: f32,
+        big: u64,
+        min: i64,
+    }
+
+    let j = "flags = 0x1F;\nnegative_hex = -0X10;\nplus = +5;\nhalf = .5;\nsmall = 1e-006;\n\
+             big = 18446744073709551615;\nmin = -9223372036854775808;";
+    let expected = Test {
+        flags: 31,
+        negative_hex: -16,
+        plus: 5,
+        half: 0.5,
+        small: 0.000001,
+        big: u64::MAX,
+        min: i64::MIN,
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+
+    let value: serde_arma::Value =
+        serde_arma::from_str("a = 0x10; b = -.25; c = 5.; d = 2E+2;").unwrap();
+    let class = value.as_class().unwrap();
+    assert_eq!(Some(16), class.get("a").and_then(serde_arma::Value::as_i64));
+    assert_eq!(Some(-0.25), class.get("b").and_then(serde_arma::Value::as_f64));
+    assert_eq!(Some(5.0), class.get("c").and_then(serde_arma::Value::as_f64));
+    assert_eq!(Some(200.0), class.get("d").and_then(serde_arma::Value::as_f64));
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Small {
+        n: u8,
+    }
+    let invalid = [
+        "n = 0x;",
+        "n = 1e;",
+        "n = .;",
+        "n = 1.2.3;",
+        "n = --1;",
+        "n = 0x1G;",
+    ];
+    for input in invalid.iter() {
+        let err = serde_arma::from_str::<serde_arma::Value>(input).unwrap_err();
+        assert!(
+            matches!(err.kind(), serde_arma::ErrorKind::InvalidNumber(_)),
+            "{}",
+            input
+        );
+    }
+    let out_of_range = [
+        "n = 18446744073709551616;",
+        "n = 0x10000000000000000;",
+        "n = 1e999;",
+    ];
+    for input in out_of_range.iter() {
+        let err = serde_arma::from_str::<serde_arma::Value>(input).unwrap_err();
+        assert!(
+            matches!(err.kind(), serde_arma::ErrorKind::NumberOutOfRange(_)),
+            "{}",
+            input
+        );
+    }
+    assert!(serde_arma::from_str::<Small>("n = 256;").is_err());
+    assert!(serde_arma::from_str::<Small>("n = -1;").is_err());
+}
+
+#[test]
+fn test_f64_precision() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        position: Vec<f64>,
+        single: f32,
+    }
+
+    let j = "position[] = {5818.1763,88.785065,11359.494};\nsingle = 0.1;";
+    let expected = Test {
+        position: vec![5818.1763, 88.785065, 11359.494],
+        single: 0.1,
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+}
+
+#[test]
+fn test_enum() {
+    use serde_repr::Deserialize_repr;
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum Side {
+        West,
+        East,
+    }
+    #[derive(Deserialize_repr, PartialEq, Debug)]
+    #[repr(u8)]
+    enum Kind {
+        Object = 1,
+        Group = 2,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Marker {
+        name: String,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum Entity {
+        Marker(Marker),
+        Trigger { radius: f32 },
+        Waypoint(u32),
+        Position(f32, f32),
+        Empty,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        side: Side,
+        #[serde(rename = "type")]
+        kind: Kind,
+        first: Entity,
+        second: Entity,
+        third: Entity,
+        fourth: Entity,
+        fifth: Entity,
+    }
+
+    let j = r#"side = "East";
+type = 2;
+class first {
+    class Marker {
+        name = "start";
+    };
+};
+class second {
+    class Trigger: Base {
+        radius = 5;
+    };
+};
+class third {
+    Waypoint = 3;
+};
+class fourth { Position[] = {1, 2}; };
+class fifth {
+    class Empty {};
+};
+"#;
+    let expected = Test {
+        side: Side::East,
+        kind: Kind::Group,
+        first: Entity::Marker(Marker {
+            name: "start".to_string(),
+        }),
+        second: Entity::Trigger { radius: 5.0 },
+        third: Entity::Waypoint(3),
+        fourth: Entity::Position(1.0, 2.0),
+        fifth: Entity::Empty,
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+    let value: serde_arma::Value = serde_arma::from_str(j).unwrap();
+    assert_eq!(expected, serde_arma::from_value(value).unwrap());
+
+    let err = serde_arma::from_str::<Test>(r#"side = "North";"#).unwrap_err();
+    assert_eq!(Some("side"), err.path());
+    let j = "side = \"West\";\ntype = 1;\nclass first {\n    Unknown = 1;\n};";
+    let err = serde_arma::from_str::<Test>(j).unwrap_err();
+    assert_eq!((Some(4), Some(5)), (err.line(), err.column()));
+
+    // Unit variants can be bare words, and a class can be read as a list of
+    // variants named after its entries.
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Entities {
+        side: Side,
+        #[serde(rename = "Entities")]
+        entities: Vec<Entity>,
+    }
+    let j = r#"side = West;
+class Entities {
+    class Marker {
+        name = "a";
+    };
+    class Trigger {
+        radius = 5;
+    };
+    Waypoint = 3;
+    class Empty {};
+};
+"#;
+    let expected = Entities {
+        side: Side::West,
+        entities: vec![
+            Entity::Marker(Marker {
+                name: "a".to_string(),
+            }),
+            Entity::Trigger { radius: 5.0 },
+            Entity::Waypoint(3),
+            Entity::Empty,
+        ],
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+    let value: serde_arma::Value = serde_arma::from_str(j).unwrap();
+    assert_eq!(expected, serde_arma::from_value(value.clone()).unwrap());
+    let entities = value.as_class().and_then(|c| c.get("Entities")).unwrap();
+    assert_eq!(
+        expected.entities,
+        serde_arma::from_value::<Vec<Entity>>(entities.clone()).unwrap()
+    );
+    let entities: Vec<Entity> = serde_arma::from_str("class Empty {};\nWaypoint = 1;").unwrap();
+    assert_eq!(vec![Entity::Empty, Entity::Waypoint(1)], entities);
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Item {
+        id: u32,
+        #[serde(flatten)]
+        entity: Entity,
+    }
+    let j = "id = 1;\nclass Trigger {\n    radius = 5;\n};";
+    let expected = Item {
+        id: 1,
+        entity: Entity::Trigger { radius: 5.0 },
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+    // The parent of a unit variant is dropped, a struct variant keeps its own.
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum Object {
+        Empty,
+        Trigger {
+            #[serde(rename = "$parent")]
+            parent: Option<String>,
+            radius: u32,
+        },
+    }
+    let j = r#"class Empty: Foo {};
+class Trigger { radius = 5; };
+class Trigger: Base { radius = 6; };"#;
+    let expected = vec![
+        Object::Empty,
+        Object::Trigger {
+            parent: None,
+            radius: 5,
+        },
+        Object::Trigger {
+            parent: Some("Base".to_string()),
+            radius: 6,
+        },
+    ];
+    assert_eq!(expected, serde_arma::from_str::<Vec<Object>>(j).unwrap());
+
+    let j = "side = West;\nclass Entities {\n    Waypoint = 3;\n";
+    let err = serde_arma::from_str::<Entities>(j).unwrap_err();
+    assert_eq!(&serde_arma::ErrorKind::ExpectedMapEnd, err.kind());
+}
+
+#[test]
+fn test_unquoted_strings() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        model: String,
+        scope: String,
+        names: Vec<String>,
+        side: String,
+    }
+
+    let j = r#"model = \A3\data\model.p3d;
+scope = public; // after macro expansion
+names[] = {first, second item , "third"};
+side = frontline;"#;
+    let expected = Test {
+        model: r"\A3\data\model.p3d".to_string(),
+        scope: "public".to_string(),
+        names: vec![
+            "first".to_string(),
+            "second item".to_string(),
+            "third".to_string(),
+        ],
+        side: "frontline".to_string(),
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+
+    let value: serde_arma::Value = serde_arma::from_str("scope = public;").unwrap();
+    assert_eq!(
+        Some("public"),
+        value
+            .as_class()
+            .and_then(|c| c.get("scope"))
+            .and_then(serde_arma::Value::as_str)
+    );
+
+    let mut deserializer = serde_arma::Deserializer::from_str(j).strict_strings(true);
+    let err = Test::deserialize(&mut deserializer).unwrap_err();
+    assert_eq!(&serde_arma::ErrorKind::UnquotedString, err.kind());
+    assert_eq!((Some(1), Some(9)), (err.line(), err.column()));
+}
+
+#[test]
+fn test_literal_mode() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        enabled: bool,
+        hidden: bool,
+        side: String,
+        name: Option<String>,
+        texture: String,
+    }
+
+    let j = r#"enabled = 1;
+hidden = 0;
+side = true;
+name = null;
+texture = falloff;"#;
+    let expected = Test {
+        enabled: true,
+        hidden: false,
+        side: "true".to_string(),
+        name: Some("null".to_string()),
+        texture: "falloff".to_string(),
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+
+    let value: serde_arma::Value = serde_arma::from_str("side = true;").unwrap();
+    assert_eq!(
+        Some("true"),
+        value
+            .as_class()
+            .and_then(|c| c.get("side"))
+            .and_then(serde_arma::Value::as_str)
+    );
+
+    let err = serde_arma::from_str::<Test>("enabled = 2;").unwrap_err();
+    assert_eq!(&serde_arma::ErrorKind::ExpectedBoolean, err.kind());
+    assert_eq!(Some("enabled"), err.path());
+
+    // Resolved trees fill booleans the same way.
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Flags {
+        enabled: bool,
+        hidden: bool,
+    }
+    let j = "class Base { enabled = 1; hidden = 1; };\nclass Flags: Base { hidden = 0; };";
+    let value: serde_arma::Value = serde_arma::from_str(j).unwrap();
+    let flags = value.resolve().unwrap().as_class().unwrap().get("Flags").cloned();
+    let expected = Flags {
+        enabled: true,
+        hidden: false,
+    };
+    assert_eq!(expected, serde_arma::from_value(flags.unwrap()).unwrap());
+    let value: serde_arma::Value = serde_arma::from_str("enabled = 2; hidden = 0;").unwrap();
+    let err = serde_arma::from_value::<Flags>(value).unwrap_err();
+    assert_eq!(&serde_arma::ErrorKind::ExpectedBoolean, err.kind());
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Lenient {
+        enabled: bool,
+        hidden: bool,
+        name: Option<String>,
+        texture: String,
+    }
+
+    let j = r#"enabled = true;
+hidden = 0;
+name = null;
+texture = falloff;"#;
+    let mut deserializer =
+        serde_arma::Deserializer::from_str(j).literals(serde_arma::LiteralMode::Lenient);
+    let expected = Lenient {
+        enabled: true,
+        hidden: false,
+        name: None,
+        texture: "falloff".to_string(),
+    };
+    assert_eq!(expected, Lenient::deserialize(&mut deserializer).unwrap());
+}
+
+#[test]
+fn test_case_insensitive() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Meta {
+        author: String,
+        #[serde(rename = "isFinal")]
+        is_final: bool,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        version: u32,
+        #[serde(rename = "AddonsMetaData")]
+        addons_meta_data: Meta,
+    }
+
+    let j = r#"Version = 12;
+class addonsmetadata {
+    AUTHOR = "someone";
+    isfinal = 1;
+};"#;
+    let expected = Test {
+        version: 12,
+        addons_meta_data: Meta {
+            author: "someone".to_string(),
+            is_final: true,
+        },
+    };
+    let mut deserializer = serde_arma::Deserializer::from_str(j).case_insensitive(true);
+    assert_eq!(expected, Test::deserialize(&mut deserializer).unwrap());
+
+    let err = serde_arma::from_str::<Test>(j).unwrap_err();
+    assert_eq!(
+        &serde_arma::ErrorKind::Message("missing field `version`".to_string()),
+        err.kind()
+    );
+}
+
+#[test]
+fn test_f32_out_of_range() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        a: f32,
+    }
+
+    let err = serde_arma::from_str::<Test>("a = 1e39;").unwrap_err();
+    assert_eq!(
+        &serde_arma::ErrorKind::NumberOutOfRange("1e39".to_string()),
+        err.kind()
+    );
+    assert_eq!((Some(1), Some(5)), (err.line(), err.column()));
+    let expected = Test { a: 3.4e38 };
+    assert_eq!(expected, serde_arma::from_str("a = 3.4e38;").unwrap());
+}
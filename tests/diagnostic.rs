@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_arma::Preprocessor;
+
+#[derive(Deserialize, Debug)]
+#[allow(dead_code)]
+struct Test {
+    numbers: Vec<u8>,
+    int: u32,
+}
+
+#[test]
+fn test_missing_semicolon() {
+    let j = "numbers[] = {1, 2}\nint = 1;\n";
+    let err = serde_arma::from_str::<Test>(j).unwrap_err();
+    let report = err.diagnostic(j).file_name("config.cpp").to_string();
+    assert_eq!(
+        "error: expected `;` after the entry in `numbers`
+ --> config.cpp:1:19
+  |
+1 | numbers[] = {1, 2}
+  |                   ^ missing `;` after the array
+",
+        report
+    );
+}
+
+#[test]
+fn test_array_comma() {
+    let j = "numbers[] = {1, 2};\n\tint = 1;\nbad[] = {\"a\" \"b\"};";
+    let err = serde_arma::from_str::<serde_arma::Value>(j).unwrap_err();
+    let report = err.diagnostic(j).to_string();
+    assert_eq!(
+        "error: expected `,` between array elements in `bad`
+ --> <input>:3:14
+  |
+3 | bad[] = {\"a\" \"b\"};
+  |              ^ array elements are separated by `,`
+",
+        report
+    );
+}
+
+#[test]
+fn test_without_position() {
+    let value: serde_arma::Value = serde_arma::from_str("class A: B {};").unwrap();
+    let err = value.resolve().unwrap_err();
+    assert_eq!(
+        "error: parent class `B` is not defined
+  = hint: define `B` before inheriting from it, or declare it with `class B;`
+",
+        err.diagnostic("").to_string()
+    );
+}
+
+#[test]
+fn test_missing_semicolon_in_macro() {
+    let j = "#define ENTRY(name) name = 1 b = 2\nc = 0;\n  ENTRY(a)\n";
+    let text = Preprocessor::new(HashMap::<String, String>::new())
+        .process_str("config.cpp", j)
+        .unwrap();
+    let err = serde_arma::from_str::<serde_arma::Value>(text.text()).unwrap_err();
+    let err = text.translate(err);
+    assert_eq!(
+        "error: expected `;` after the entry in `a`
+ --> config.cpp:3:11
+  |
+3 |   ENTRY(a)
+  |           ^ missing `;` after the value
+  = note: in the expansion of `ENTRY`
+",
+        text.diagnostic(&err).to_string()
+    );
+}
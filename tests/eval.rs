@@ -0,0 +1,96 @@
+use serde::Deserialize;
+use serde_arma::ErrorKind;
+
+#[test]
+fn test_eval() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Param {
+        title: String,
+        values: Vec<u32>,
+        default: u32,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        value: u32,
+        ratio: f64,
+        name: String,
+        flag: bool,
+        #[serde(rename = "Duration")]
+        duration: Param,
+    }
+
+    let j = r#"__EXEC(minutes = 5; _prefix = "Mission")
+value = __EVAL(60*5);
+ratio = __EVAL((1 + 2) / 4 ^ 2);
+name = __EVAL(_prefix + " " + str (minutes max 3));
+flag = __EVAL(minutes > 2);
+class Duration {
+    __EXEC(MINUTES = minutes * 2);
+    title = __EVAL("Duration (" + str minutes + " minutes)");
+    values[] = {__EVAL(minutes * 60), __EVAL(floor 7.9 mod 4), 0x10};
+    default = __EVAL(-(-minutes));
+};"#;
+    let expected = Test {
+        value: 300,
+        ratio: 0.1875,
+        name: "Mission 5".to_string(),
+        flag: true,
+        duration: Param {
+            title: "Duration (10 minutes)".to_string(),
+            values: vec![600, 3, 16],
+            default: 10,
+        },
+    };
+    assert_eq!(expected, serde_arma::from_str(j).unwrap());
+
+    let value: serde_arma::Value = serde_arma::from_str("a = __EVAL(1 / 4);").unwrap();
+    assert_eq!(
+        Some(0.25),
+        value
+            .as_class()
+            .and_then(|c| c.get("a"))
+            .and_then(serde_arma::Value::as_f64)
+    );
+
+    let j = "a = __EVAL(1e-5 * 2); b = __EVAL(2.5E+2-1); c = __EVAL(0x1e-1);";
+    let value: serde_arma::Value = serde_arma::from_str(j).unwrap();
+    let class = value.as_class().unwrap();
+    let number = |name| class.get(name).and_then(serde_arma::Value::as_f64);
+    assert_eq!(Some(2e-5), number("a"));
+    assert_eq!(Some(249.0), number("b"));
+    assert_eq!(Some(29.0), number("c"));
+}
+
+#[test]
+fn test_eval_errors() {
+    let cases = [
+        ("a = __EVAL(1 / 0);", "division by zero", 1, 5),
+        (
+            "a = 1;\nb = __EVAL(x + 1);",
+            "`x` is not a variable or a supported command",
+            2,
+            5,
+        ),
+        (
+            "a = __EVAL(\"a\" + 1);",
+            "expected a number, found \"a\"",
+            1,
+            5,
+        ),
+        ("a = __EVAL([1, 2]);", "unsupported `[`", 1, 5),
+        ("a = __EVAL(1e-);", "invalid number `1e-`", 1, 5),
+        (
+            "__EXEC(call compile \"x\")",
+            "`call` is not a variable or a supported command",
+            1,
+            1,
+        ),
+        ("a = __EVAL(x = 1);", "the code gives no value", 1, 5),
+    ];
+    for (j, msg, line, column) in cases.iter() {
+        let err = serde_arma::from_str::<serde_arma::Value>(j).unwrap_err();
+        assert_eq!(&ErrorKind::Eval(msg.to_string()), err.kind());
+        assert_eq!((Some(*line), Some(*column)), (err.line(), err.column()));
+    }
+}
@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_arma::{FileSystem, IncludeResolver};
+
+// A directory below the system's temporary directory, removed again when
+// the test is done.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("serde_arma_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        TempDir(dir)
+    }
+
+    fn write(&self, path: &str, text: &str) {
+        let path = self.0.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    fn path(&self, path: &str) -> String {
+        self.0.join(path).to_string_lossy().into_owned()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn test_include_resolver() {
+    let dir = TempDir::new("include");
+    dir.write(
+        "mod/addons/main/config.cpp",
+        r#"#include "Script_Component.hpp"
+name = QUOTE(ADDON);
+key = DIK_ESCAPE;"#,
+    );
+    dir.write(
+        "mod/addons/main/script_component.hpp",
+        "#define COMPONENT main\n#include \"\\x\\cba\\addons\\main\\script_macros_common.hpp\"\n#include \"\\A3\\UI_F\\hpp\\defineDIKCodes.inc\"",
+    );
+    dir.write(
+        "cba/addons/main/Script_Macros_Common.hpp",
+        "#define ADDON DOUBLES(cba,COMPONENT)\n#define DOUBLES(a,b) a##_##b\n#define QUOTE(a) #a",
+    );
+    dir.write(
+        "p/a3/ui_f/hpp/definedikcodes.inc",
+        "#define DIK_ESCAPE 0x01",
+    );
+
+    let resolver = IncludeResolver::new()
+        .prefix(r"\x\cba\addons", dir.path("cba/addons"))
+        .prefix("", dir.path("p"));
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Test {
+        name: String,
+        key: u8,
+    }
+
+    let test: Test =
+        serde_arma::from_file(&resolver, &dir.path("mod/addons/main/config.cpp")).unwrap();
+    assert_eq!(
+        Test {
+            name: "cba_main".to_string(),
+            key: 1,
+        },
+        test
+    );
+
+    // Relative paths are tried before the prefixes.
+    dir.write(
+        "mod/addons/main/x/cba/addons/main/script_macros_common.hpp",
+        "",
+    );
+    let from = dir.path("mod/addons/main/config.cpp");
+    let resolved = resolver
+        .resolve(r"x\cba\addons\main\script_macros_common.hpp", &from)
+        .unwrap();
+    assert_eq!(
+        Path::new(&dir.path("mod/addons/main/x/cba/addons/main/script_macros_common.hpp")),
+        Path::new(&resolved)
+    );
+    let resolved = resolver
+        .resolve(r"\x\cba\addons\main\script_macros_common.hpp", &from)
+        .unwrap();
+    assert_eq!(
+        Path::new(&dir.path("cba/addons/main/Script_Macros_Common.hpp")),
+        Path::new(&resolved)
+    );
+    assert!(resolver
+        .resolve(r"\x\ace\addons\main\script_macros.hpp", &from)
+        .is_err());
+}
@@ -1,3 +1,5 @@
+#![allow(non_snake_case)]
+
 use serde::Deserialize;
 
 use std::collections::HashMap;
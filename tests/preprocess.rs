@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_arma::{ErrorKind, Preprocessor};
+
+fn files(list: &[(&str, &str)]) -> HashMap<String, String> {
+    list.iter()
+        .map(|(name, text)| (name.to_string(), text.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_macros() {
+    let fs = files(&[
+        (
+            "addons/main/config.cpp",
+            r#"#include "script_component.hpp"
+class CfgPatches {
+    class ADDON {
+        name = QUOTE(ADDON);
+        author = "PREFIX"; // not expanded in strings
+        units[] = {DOUBLES(PREFIX,unit)};
+        version = VERSION;
+    };
+};"#,
+        ),
+        (
+            "addons/main/script_component.hpp",
+            "#define COMPONENT main\n#include \"\\addons\\macros.hpp\"",
+        ),
+        (
+            "addons/macros.hpp",
+            r#"#define PREFIX cba
+#define DOUBLES(var1,var2) var1##_##var2
+#define ADDON DOUBLES(PREFIX,COMPONENT)
+#define QUOTE(var1) #var1
+#define VERSION \
+    1.2"#,
+        ),
+    ]);
+    let text = Preprocessor::new(&fs)
+        .process("addons/main/config.cpp")
+        .unwrap();
+    let expected = r#"
+
+
+
+
+
+class CfgPatches {
+    class cba_main {
+        name = "cba_main";
+        author = "PREFIX";
+        units[] = {cba_unit};
+        version = 1.2;
+    };
+};"#;
+    let lines: Vec<&str> = text.text().split('\n').map(str::trim_end).collect();
+    assert_eq!(expected, lines.join("\n"));
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Patch {
+        name: String,
+        units: Vec<String>,
+        version: f32,
+    }
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Config {
+        #[serde(rename = "CfgPatches")]
+        patches: HashMap<String, Patch>,
+    }
+
+    let config: Config = serde_arma::from_file(&fs, "addons/main/config.cpp").unwrap();
+    assert_eq!(
+        Some(&Patch {
+            name: "cba_main".to_string(),
+            units: vec!["cba_unit".to_string()],
+            version: 1.2,
+        }),
+        config.patches.get("cba_main")
+    );
+}
+
+#[test]
+fn test_conditionals() {
+    let j = r#"#define DEBUG
+#ifdef DEBUG
+debug = 1;
+#ifndef RELEASE
+release = 0;
+#else
+release = 1;
+#endif
+#else
+debug = 0;
+#endif
+#undef DEBUG
+#ifdef DEBUG
+undefined = 0;
+#if __A3_DEBUG__ > 1
+nested = 0;
+#endif
+undefined = 1;
+#endif
+/*
+#include "missing.hpp"
+*/
+done = 1;"#;
+    let mut preprocessor = Preprocessor::new(files(&[]));
+    let text = preprocessor.process_str("config.cpp", j).unwrap();
+    let lines: Vec<&str> = text.text().lines().map(str::trim).collect();
+    assert_eq!(j.lines().count(), lines.len());
+    assert_eq!(
+        vec!["debug = 1;", "release = 0;", "done = 1;"],
+        lines
+            .into_iter()
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+    );
+    assert!(!preprocessor.is_defined("DEBUG"));
+
+    let text = Preprocessor::new(files(&[]))
+        .define("RELEASE", "")
+        .process_str("config.cpp", "#ifndef RELEASE\na = 1;\n#endif\nb = 2;")
+        .unwrap();
+    assert_eq!("\n\n\nb = 2;", text.text());
+}
+
+#[test]
+fn test_preprocess_errors() {
+    let fs = files(&[
+        ("config.cpp", "a = 1;\n#include \"other.hpp\""),
+        ("other.hpp", "b = 2;\n\n  #include \"missing.hpp\""),
+        ("loop.hpp", "#include \"loop.hpp\""),
+    ]);
+    let err = Preprocessor::new(&fs).process("config.cpp").unwrap_err();
+    assert_eq!(
+        &ErrorKind::IncludeNotFound("missing.hpp".to_string()),
+        err.kind()
+    );
+    assert_eq!(Some("other.hpp"), err.file());
+    assert_eq!((Some(3), Some(3)), (err.line(), err.column()));
+
+    let err = Preprocessor::new(&fs).process("loop.hpp").unwrap_err();
+    assert_eq!(
+        &ErrorKind::RecursiveInclude("loop.hpp".to_string()),
+        err.kind()
+    );
+
+    let cases = [
+        ("#ifdef A\na = 1;", ErrorKind::UnterminatedConditional, 2, 7),
+        ("#endif", ErrorKind::InvalidDirective("endif"), 1, 1),
+        (
+            "#if A\n#endif",
+            ErrorKind::UnknownDirective("if".to_string()),
+            1,
+            1,
+        ),
+        (
+            "#define F(a, b) a\nx = F(1);",
+            ErrorKind::MacroArguments("F".to_string(), 2),
+            2,
+            5,
+        ),
+        ("#define F(a) a\nx = F(1;", ErrorKind::UnexpectedEof, 2, 5),
+    ];
+    for (j, kind, line, column) in cases.iter() {
+        let err = Preprocessor::new(&fs)
+            .process_str("config.cpp", j)
+            .unwrap_err();
+        assert_eq!(kind, err.kind());
+        assert_eq!((Some(*line), Some(*column)), (err.line(), err.column()));
+    }
+    assert_eq!(
+        "macro `F` takes 1 argument",
+        ErrorKind::MacroArguments("F".to_string(), 1).to_string()
+    );
+    assert_eq!(
+        "macro `F` takes 2 arguments",
+        ErrorKind::MacroArguments("F".to_string(), 2).to_string()
+    );
+}
+
+#[test]
+fn test_source_map() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Test {
+        first: u32,
+        second: u32,
+        count: u32,
+    }
+
+    let fs = files(&[
+        (
+            "config.cpp",
+            "#define COUNT \"many\"\nfirst = 1;\n#include \"XEH_PREP.hpp\"\ncount = COUNT;\n",
+        ),
+        ("XEH_PREP.hpp", "// functions\nsecond = \"two\";\n"),
+    ]);
+    let preprocessed = Preprocessor::new(&fs).process("config.cpp").unwrap();
+    let offset = preprocessed.text().find("\"two\"").unwrap();
+    let location = preprocessed.source_map().locate(offset).unwrap();
+    assert_eq!("XEH_PREP.hpp", location.file);
+    assert_eq!((2, 10), (location.position.line, location.position.column));
+    assert!(location.macros.is_empty());
+
+    let err = serde_arma::from_str::<Test>(preprocessed.text()).unwrap_err();
+    let err = preprocessed.translate(err);
+    assert_eq!(Some("XEH_PREP.hpp"), err.file());
+    assert_eq!((Some(2), Some(10)), (err.line(), err.column()));
+
+    let fs = files(&[(
+        "config.cpp",
+        "#define COUNT \"many\"\nfirst = 1;\nsecond = 2;\ncount = COUNT;\n",
+    )]);
+    let err = serde_arma::from_file::<_, Test>(&fs, "config.cpp").unwrap_err();
+    assert_eq!(Some("config.cpp"), err.file());
+    assert_eq!((Some(4), Some(9)), (err.line(), err.column()));
+    assert_eq!(&["COUNT".to_string()], err.macros());
+
+    let preprocessed = Preprocessor::new(&fs).process("config.cpp").unwrap();
+    let report = preprocessed.diagnostic(&err).to_string();
+    assert_eq!(
+        "error: invalid number `\"many\"` in `count`
+ --> config.cpp:4:9
+  |
+4 | count = COUNT;
+  |         ^
+  = note: in the expansion of `COUNT`
+",
+        report
+    );
+}
@@ -0,0 +1,183 @@
+use serde::Deserialize;
+
+use serde_arma::Value;
+
+#[test]
+fn test_resolve() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Config {
+        #[serde(rename = "CfgVehicles")]
+        vehicles: CfgVehicles,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct CfgVehicles {
+        #[serde(rename = "Car")]
+        car: Vehicle,
+        #[serde(rename = "MyCar")]
+        my_car: Vehicle,
+    }
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Vehicle {
+        #[serde(rename = "$parent")]
+        parent: Option<String>,
+        speed: u32,
+        armor: u32,
+    }
+
+    let j = r#"class CfgVehicles {
+    class Car {
+        speed = 10;
+        armor = 5;
+    };
+    class MyCar: Car {
+        speed = 20;
+    };
+};
+"#;
+    let value: Value = serde_arma::from_str(j).unwrap();
+    let config: Config = serde_arma::from_value(value.resolve().unwrap()).unwrap();
+    let expected = CfgVehicles {
+        car: Vehicle {
+            parent: None,
+            speed: 10,
+            armor: 5,
+        },
+        my_car: Vehicle {
+            parent: Some("Car".to_string()),
+            speed: 20,
+            armor: 5,
+        },
+    };
+    assert_eq!(expected, config.vehicles);
+}
+
+#[test]
+fn test_resolve_scopes() {
+    let j = r#"class Base {
+    value = 1;
+    class Turrets {
+        class MainTurret {
+            gunner = 1;
+        };
+    };
+};
+class CfgVehicles {
+    class Base {
+        value = 2;
+    };
+    class Tank: Base {};
+};
+class Top: Base {};
+"#;
+    let value: Value = serde_arma::from_str(j).unwrap();
+    let resolved = value.resolve().unwrap();
+    let root = resolved.as_class().unwrap();
+    let vehicles = root.get("CfgVehicles").and_then(Value::as_class).unwrap();
+
+    // The sibling `Base` inside `CfgVehicles` is found before the one at the root.
+    let tank = vehicles.get("Tank").and_then(Value::as_class).unwrap();
+    assert_eq!(Some(2), tank.get("value").and_then(Value::as_i64));
+    assert_eq!(None, tank.get("Turrets"));
+    let top = root.get("Top").and_then(Value::as_class).unwrap();
+    assert_eq!(Some(1), top.get("value").and_then(Value::as_i64));
+    assert!(top.get("Turrets").is_some());
+
+    let j = r#"class Base {
+    class Turrets {
+        class MainTurret {
+            gunner = 1;
+        };
+    };
+};
+class Tank: Base {
+    class Turrets: Turrets {
+        commander = 1;
+    };
+};
+"#;
+    let value: Value = serde_arma::from_str(j).unwrap();
+    let resolved = value.resolve().unwrap();
+    let turrets = resolved
+        .as_class()
+        .and_then(|c| c.get("Tank"))
+        .and_then(Value::as_class)
+        .and_then(|c| c.get("Turrets"))
+        .and_then(Value::as_class)
+        .unwrap();
+    assert_eq!(
+        vec!["MainTurret", "commander"],
+        turrets.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_resolve_missing_parent() {
+    let value: Value = serde_arma::from_str("class Child: Missing {};").unwrap();
+    assert_eq!(
+        &serde_arma::ErrorKind::UnresolvedParent("Missing".to_string()),
+        value.resolve().unwrap_err().kind()
+    );
+}
+
+#[test]
+fn test_resolve_declarations() {
+    let j = r#"class Base {
+    class Turret {};
+    value = 1;
+};
+class Man;
+class Soldier: Man {
+    speed = 1;
+};
+class Child: Base {
+    delete Turret;
+};
+"#;
+    let value: Value = serde_arma::from_str(j).unwrap();
+    let resolved = value.resolve().unwrap();
+    let root = resolved.as_class().unwrap();
+    let soldier = root.get("Soldier").and_then(Value::as_class).unwrap();
+    assert_eq!(Some(1), soldier.get("speed").and_then(Value::as_i64));
+    let child = root.get("Child").and_then(Value::as_class).unwrap();
+    assert_eq!(
+        vec!["value"],
+        child.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_resolve_array_operators() {
+    let j = r#"class Base {
+    items[] = {"a", "b"};
+    numbers[] = {1, 2, 3};
+};
+class Child: Base {
+    items[] += {"c"};
+    numbers[] -= {2};
+    fresh[] += {1};
+};
+"#;
+    let value: Value = serde_arma::from_str(j).unwrap();
+    let resolved = value.resolve().unwrap();
+    let child = resolved
+        .as_class()
+        .and_then(|c| c.get("Child"))
+        .and_then(Value::as_class)
+        .unwrap();
+    assert_eq!(
+        Some(&Value::Array(vec![
+            Value::from("a"),
+            Value::from("b"),
+            Value::from("c")
+        ])),
+        child.get("items")
+    );
+    assert_eq!(
+        Some(&Value::Array(vec![Value::from(1), Value::from(3)])),
+        child.get("numbers")
+    );
+    assert_eq!(
+        Some(&Value::Array(vec![Value::from(1)])),
+        child.get("fresh")
+    );
+}
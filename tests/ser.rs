@@ -0,0 +1,364 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+#[test]
+fn test_struct() {
+    #[derive(Serialize)]
+    struct Test {
+        int: u32,
+        float: f32,
+        string: String,
+    }
+
+    let test = Test {
+        int: 123,
+        float: 1.5,
+        string: "Hello".to_string(),
+    };
+    let expected = r#"int = 123;
+float = 1.5;
+string = "Hello";
+"#;
+    assert_eq!(expected, serde_arma::to_string(&test).unwrap());
+}
+
+#[test]
+fn test_escape() {
+    #[derive(Serialize)]
+    struct Test {
+        escape: String,
+        newline: String,
+    }
+
+    let test = Test {
+        escape: "Hello \"World\"".to_string(),
+        newline: "one\ntwo".to_string(),
+    };
+    let expected = r#"escape = "Hello ""World""";
+newline = "one" \n "two";
+"#;
+    assert_eq!(expected, serde_arma::to_string(&test).unwrap());
+}
+
+#[test]
+fn test_class() {
+    #[derive(Serialize)]
+    struct CfgPatches {
+        my_addon: Patch,
+    }
+    #[derive(Serialize)]
+    struct Patch {
+        units: Vec<String>,
+        weapons: Vec<String>,
+        #[serde(rename = "requiredVersion")]
+        required_version: f32,
+        nested: Vec<(u8, Vec<String>)>,
+    }
+
+    let test = CfgPatches {
+        my_addon: Patch {
+            units: vec!["my_unit".to_string()],
+            weapons: Vec::new(),
+            required_version: 0.1,
+            nested: vec![(1, vec!["a".to_string(), "b".to_string()])],
+        },
+    };
+    let expected = r#"class my_addon {
+    units[] = {"my_unit"};
+    weapons[] = {};
+    requiredVersion = 0.1;
+    nested[] = {{1, {"a", "b"}}};
+};
+"#;
+    assert_eq!(expected, serde_arma::to_string(&test).unwrap());
+}
+
+#[test]
+fn test_map_and_option() {
+    #[derive(Serialize)]
+    struct Test {
+        missing: Option<u8>,
+        present: Option<u8>,
+        flag: bool,
+        map: BTreeMap<String, i32>,
+    }
+
+    let mut map = BTreeMap::new();
+    map.insert("a".to_string(), -1);
+    map.insert("b".to_string(), 2);
+    let test = Test {
+        missing: None,
+        present: Some(4),
+        flag: true,
+        map,
+    };
+    let expected = r#"present = 4;
+flag = 1;
+class map {
+    a = -1;
+    b = 2;
+};
+"#;
+    assert_eq!(expected, serde_arma::to_string(&test).unwrap());
+}
+
+#[test]
+fn test_unsupported() {
+    #[derive(Serialize)]
+    struct Child {}
+    #[derive(Serialize)]
+    struct Test {
+        children: Vec<Child>,
+    }
+
+    assert!(serde_arma::to_string(&1).is_err());
+    assert!(serde_arma::to_string(&Test {
+        children: vec![Child {}]
+    })
+    .is_err());
+}
+
+#[test]
+fn test_round_trip() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Test {
+        numbers: Vec<u8>,
+        after: String,
+        child: Child,
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Child {
+        number: u32,
+        string: String,
+    }
+
+    let test = Test {
+        numbers: vec![1, 2, 3],
+        after: "hi \"there\"".to_string(),
+        child: Child {
+            number: 123,
+            string: "Hello".to_string(),
+        },
+    };
+    let text = serde_arma::to_string(&test).unwrap();
+    assert_eq!(test, serde_arma::from_str(&text).unwrap());
+}
+
+#[test]
+fn test_pretty_bohemia() {
+    #[derive(Serialize)]
+    struct Test {
+        version: u8,
+        #[serde(rename = "Camera")]
+        camera: Camera,
+        addons: Vec<String>,
+    }
+    #[derive(Serialize)]
+    struct Camera {
+        pos: Vec<f64>,
+        aside: Vec<f64>,
+        rate: f64,
+    }
+
+    let test = Test {
+        version: 53,
+        camera: Camera {
+            pos: vec![5818.1763, 88.785065, 11359.494],
+            aside: vec![0.30084822, -1.5593832e-7, -0.95369172],
+            rate: 1e-6,
+        },
+        addons: vec!["A3_Ui_F", "ace_cargo", "ace_repair", "A3_Soft_F"]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+    };
+    let expected = "version=53;
+class Camera
+{
+\tpos[]={5818.1763,88.785065,11359.494};
+\taside[]={0.30084822,-1.5593832e-007,-0.95369172};
+\trate=1e-006;
+};
+addons[]=
+{
+\t\"A3_Ui_F\",
+\t\"ace_cargo\",
+\t\"ace_repair\",
+\t\"A3_Soft_F\"
+};
+";
+    let config = serde_arma::PrettyConfig::bohemia();
+    assert_eq!(
+        expected,
+        serde_arma::to_string_pretty(&test, config).unwrap()
+    );
+}
+
+#[test]
+fn test_pretty_options() {
+    #[derive(Serialize)]
+    struct Test {
+        numbers: Vec<u8>,
+        child: Child,
+    }
+    #[derive(Serialize)]
+    struct Child {
+        value: f64,
+    }
+
+    let test = Test {
+        numbers: vec![1, 2],
+        child: Child { value: 123456789.0 },
+    };
+    let expected = "numbers[] = {
+  1,
+  2
+};
+class child {
+  value = 1.2345679e+008;
+};
+";
+    let config = serde_arma::PrettyConfig::new()
+        .indent("  ")
+        .array_inline_limit(Some(1))
+        .float_format(serde_arma::FloatFormat::Bohemia);
+    assert_eq!(
+        expected,
+        serde_arma::to_string_pretty(&test, config).unwrap()
+    );
+}
+
+#[test]
+fn test_inheritance() {
+    #[derive(Serialize)]
+    struct CfgVehicles {
+        #[serde(rename = "Car")]
+        car: Vehicle,
+        #[serde(rename = "MyCar")]
+        my_car: Vehicle,
+    }
+    #[derive(Serialize)]
+    struct Vehicle {
+        #[serde(rename = "$parent")]
+        parent: Option<String>,
+        speed: u32,
+    }
+
+    let test = CfgVehicles {
+        car: Vehicle {
+            parent: None,
+            speed: 10,
+        },
+        my_car: Vehicle {
+            parent: Some("Car".to_string()),
+            speed: 20,
+        },
+    };
+    let expected = r#"class Car {
+    speed = 10;
+};
+class MyCar: Car {
+    speed = 20;
+};
+"#;
+    assert_eq!(expected, serde_arma::to_string(&test).unwrap());
+
+    // The parent can come after the entries.
+    #[derive(Serialize)]
+    struct Late {
+        speed: u32,
+        #[serde(rename = "Wheels")]
+        wheels: Wheels,
+        #[serde(rename = "$parent")]
+        parent: &'static str,
+    }
+    #[derive(Serialize)]
+    struct Wheels {
+        count: u32,
+    }
+    let mut test = BTreeMap::new();
+    test.insert(
+        "MyCar",
+        Late {
+            speed: 20,
+            wheels: Wheels { count: 4 },
+            parent: "Car",
+        },
+    );
+    let expected = r#"class MyCar: Car {
+    speed = 20;
+    class Wheels {
+        count = 4;
+    };
+};
+"#;
+    assert_eq!(expected, serde_arma::to_string(&test).unwrap());
+}
+
+#[test]
+fn test_enum_round_trip() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Entity {
+        Marker { name: String },
+        Waypoint(u32),
+        Position(f32, f32),
+        Empty,
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Test {
+        marker: Entity,
+        waypoint: Entity,
+        position: Entity,
+        empty: Entity,
+    }
+
+    let test = Test {
+        marker: Entity::Marker {
+            name: "start".to_string(),
+        },
+        waypoint: Entity::Waypoint(3),
+        position: Entity::Position(1.5, 2.0),
+        empty: Entity::Empty,
+    };
+    let text = serde_arma::to_string(&test).unwrap();
+    assert_eq!(test, serde_arma::from_str(&text).unwrap());
+}
+
+#[test]
+fn test_float_round_trip() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Test {
+        large: f64,
+        small: f64,
+        whole: f64,
+        negative: f64,
+        single: f32,
+    }
+
+    let test = Test {
+        large: 1e20,
+        small: 1e-300,
+        whole: 5.0,
+        negative: -2.5e-7,
+        single: 0.1,
+    };
+    let text = serde_arma::to_string(&test).unwrap();
+    assert_eq!(
+        "large = 1e20;\nsmall = 1e-300;\nwhole = 5.0;\nnegative = -2.5e-7;\nsingle = 0.1;\n",
+        text
+    );
+    assert_eq!(test, serde_arma::from_str(&text).unwrap());
+    let config = serde_arma::PrettyConfig::bohemia();
+    let text = serde_arma::to_string_pretty(&test, config).unwrap();
+    assert_eq!(
+        "large=1e+020;\nsmall=1e-300;\nwhole=5.0;\nnegative=-2.5e-007;\nsingle=0.1;\n",
+        text
+    );
+    assert_eq!(test, serde_arma::from_str(&text).unwrap());
+
+    let value: serde_arma::Value = serde_arma::from_str(&text).unwrap();
+    let whole = value.as_class().and_then(|c| c.get("whole")).unwrap();
+    assert_eq!(None, whole.as_i64());
+    assert_eq!(Some(5.0), whole.as_f64());
+}
@@ -0,0 +1,135 @@
+use serde_arma::{Document, ErrorKind, Node};
+
+const CONFIG: &str = r#"#include "script_component.hpp"
+
+#define VERSION_ARRAY 1,2,3
+#define MY_MACRO(a) \
+    class a { \
+        scope = 2; \
+    }
+
+/* Patches */
+class CfgPatches {
+    class ADDON {
+        name = COMPONENT_NAME;
+        units[] = {};
+        requiredVersion = REQUIRED_VERSION;
+        requiredAddons[] = {"cba_main"}; // needs CBA
+        author = ECSTRING(common,Author);
+        VERSION_CONFIG;
+    };
+};
+
+class CfgVehicles {
+    class Man;
+    class CAManBase: Man {
+        class ACE_Actions {
+            #ifdef DEBUG_MODE_FULL
+            class GVAR(debug) {};
+            #endif
+        };
+    };
+    delete OldVehicle;
+    MY_MACRO(Vehicle);
+};
+
+PREP(init);
+__EXEC(x = 1)
+magazines[] += {QGVAR(mag), "other;mag"};
+class Extended_PreInit_EventHandlers {
+    class ADDON {
+        init = QUOTE(call COMPILE_FILE(XEH_preInit));
+    };
+};
+"#;
+
+#[test]
+fn test_round_trip() {
+    let example = include_str!("example.hpp");
+    assert_eq!(example, Document::parse(example).unwrap().to_string());
+    let document = Document::parse(CONFIG).unwrap();
+    assert_eq!(CONFIG, document.to_string());
+
+    let directives: Vec<_> = document
+        .nodes()
+        .iter()
+        .filter_map(|node| match node {
+            Node::Directive(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            "#include \"script_component.hpp\"",
+            "#define VERSION_ARRAY 1,2,3",
+            "#define MY_MACRO(a) \\\n    class a { \\\n        scope = 2; \\\n    }",
+        ],
+        directives
+    );
+    let opaque: Vec<_> = document
+        .nodes()
+        .iter()
+        .filter_map(|node| match node {
+            Node::Opaque(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(vec!["PREP(init);", "__EXEC(x = 1)"], opaque);
+}
+
+#[test]
+fn test_edit() {
+    let mut document = Document::parse(CONFIG).unwrap();
+    let patch = document
+        .class_mut("cfgpatches")
+        .and_then(|c| c.class_mut("ADDON"))
+        .unwrap();
+    assert_eq!(
+        "REQUIRED_VERSION",
+        patch.property_mut("requiredVersion").unwrap().value()
+    );
+    patch
+        .property_mut("requiredVersion")
+        .unwrap()
+        .set_value("2.14");
+    patch
+        .push("\n        url = \"https://example.com\";")
+        .unwrap();
+    document
+        .property_mut("magazines")
+        .unwrap()
+        .set_value("{QGVAR(mag)}");
+    document
+        .class_mut("CfgVehicles")
+        .unwrap()
+        .push("\n    class Car;")
+        .unwrap();
+
+    let expected = CONFIG
+        .replace("= REQUIRED_VERSION;", "= 2.14;")
+        .replace(
+            "        VERSION_CONFIG;\n",
+            "        VERSION_CONFIG;\n        url = \"https://example.com\";\n",
+        )
+        .replace("{QGVAR(mag), \"other;mag\"}", "{QGVAR(mag)}")
+        .replace(
+            "    MY_MACRO(Vehicle);\n",
+            "    MY_MACRO(Vehicle);\n    class Car;\n",
+        );
+    assert_eq!(expected, document.to_string());
+}
+
+#[test]
+fn test_syntax_errors() {
+    let cases = [
+        ("class A {\n    a = 1;\n", ErrorKind::UnexpectedEof, 3, 1),
+        ("a = 1;\n};", ErrorKind::TrailingCharacters, 2, 1),
+        ("a = \"b;", ErrorKind::UnexpectedEof, 1, 8),
+        ("/* a", ErrorKind::UnexpectedEof, 1, 5),
+    ];
+    for (text, kind, line, column) in cases.iter() {
+        let err = Document::parse(text).unwrap_err();
+        assert_eq!(kind, err.kind());
+        assert_eq!((Some(*line), Some(*column)), (err.line(), err.column()));
+    }
+}
@@ -0,0 +1,202 @@
+use serde_arma::{Class, Number, Value};
+
+#[test]
+fn test_value() {
+    let j = r#"version = 53;
+name = "test";
+pos[] = {1.5, -2, {"nested"}};
+class child {
+    number = 123;
+    empty[] = {};
+};
+"#;
+    let value: Value = serde_arma::from_str(j).unwrap();
+    let root = value.as_class().unwrap();
+    assert_eq!(
+        vec!["version", "name", "pos", "child"],
+        root.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>()
+    );
+    assert_eq!(Some(&Value::from(53)), root.get("version"));
+    assert_eq!(Some("test"), root.get("name").and_then(Value::as_str));
+    assert_eq!(
+        Some(&Value::Array(vec![
+            Value::Number(Number::from(1.5)),
+            Value::Number(Number::from(-2)),
+            Value::Array(vec![Value::from("nested")]),
+        ])),
+        root.get("pos")
+    );
+    let child = root.get("child").and_then(Value::as_class).unwrap();
+    assert_eq!(Some(123), child.get("number").and_then(Value::as_i64));
+    assert_eq!(Some(&Value::Array(Vec::new())), child.get("empty"));
+}
+
+#[test]
+fn test_value_round_trip() {
+    let mut child = Class::new();
+    child.insert("number", Value::from(123));
+    child.insert(
+        "names",
+        Value::from(vec![Value::from("a"), Value::from("b")]),
+    );
+    let mut root = Class::new();
+    root.insert("float", Value::from(0.5));
+    root.insert("child", Value::from(child));
+    let root = Value::Class(root);
+
+    let text = serde_arma::to_string(&root).unwrap();
+    let expected = r#"float = 0.5;
+class child {
+    number = 123;
+    names[] = {"a", "b"};
+};
+"#;
+    assert_eq!(expected, text);
+    assert_eq!(root, serde_arma::from_str::<Value>(&text).unwrap());
+
+    let j = "requiredVersion = 1.0;\nversion = 1;\nzero = 0.0;\nlarge = 1e20;\n";
+    for j in [j, include_str!("example.hpp")].iter() {
+        let value: Value = serde_arma::from_str(j).unwrap();
+        let text = serde_arma::to_string(&value).unwrap();
+        assert_eq!(value, serde_arma::from_str::<Value>(&text).unwrap());
+        let config = serde_arma::PrettyConfig::bohemia();
+        let text = serde_arma::to_string_pretty(&value, config).unwrap();
+        assert_eq!(value, serde_arma::from_str::<Value>(&text).unwrap());
+    }
+}
+
+#[test]
+fn test_value_mission() {
+    use std::fs;
+    let contents = fs::read_to_string("tests/example.hpp").unwrap();
+
+    let value: Value = serde_arma::from_str(&contents).unwrap();
+    let text = serde_arma::to_string(&value).unwrap();
+    assert_eq!(value, serde_arma::from_str::<Value>(&text).unwrap());
+}
+
+#[test]
+fn test_value_inheritance() {
+    let j = r#"class Base {};
+class Child:Base
+{
+    speed = 20;
+};
+"#;
+    let value: Value = serde_arma::from_str(j).unwrap();
+    let root = value.as_class().unwrap();
+    assert_eq!(
+        None,
+        root.get("Base").and_then(Value::as_class).unwrap().parent()
+    );
+    let child = root.get("Child").and_then(Value::as_class).unwrap();
+    assert_eq!(Some("Base"), child.parent());
+    assert_eq!(Some(20), child.get("speed").and_then(Value::as_i64));
+
+    let expected = r#"class Base {
+};
+class Child: Base {
+    speed = 20;
+};
+"#;
+    let text = serde_arma::to_string(&value).unwrap();
+    assert_eq!(expected, text);
+    assert_eq!(value, serde_arma::from_str::<Value>(&text).unwrap());
+}
+
+#[test]
+fn test_value_declarations() {
+    let j = r#"class CfgVehicles {
+    class Man;
+    delete Car;
+    class MySoldier: Man {
+    };
+};
+"#;
+    let value: Value = serde_arma::from_str(j).unwrap();
+    let vehicles = value
+        .as_class()
+        .and_then(|c| c.get("CfgVehicles"))
+        .and_then(Value::as_class)
+        .unwrap();
+    assert_eq!(Some(&Value::External), vehicles.get("Man"));
+    assert_eq!(Some(&Value::Delete), vehicles.get("Car"));
+    assert_eq!(j, serde_arma::to_string(&value).unwrap());
+}
+
+#[test]
+fn test_value_array_operators() {
+    let j = r#"items[] += {"a", "b"};
+numbers[] -= {1};
+"#;
+    let value: Value = serde_arma::from_str(j).unwrap();
+    let root = value.as_class().unwrap();
+    assert_eq!(
+        Some(&Value::Append(vec![Value::from("a"), Value::from("b")])),
+        root.get("items")
+    );
+    assert_eq!(
+        Some(&Value::Remove(vec![Value::from(1)])),
+        root.get("numbers")
+    );
+    assert_eq!(j, serde_arma::to_string(&value).unwrap());
+}
+
+#[test]
+fn test_value_numbers() {
+    let j = "position[] = {5818.1763,88.785065,11359.494};\nflags = 0x1F;\nsmall = 1e-006;\n";
+    let value: Value = serde_arma::from_str(j).unwrap();
+    let root = value.as_class().unwrap();
+    let position: Vec<f64> = root
+        .get("position")
+        .and_then(Value::as_array)
+        .unwrap()
+        .iter()
+        .filter_map(Value::as_f64)
+        .collect();
+    assert_eq!(vec![5818.1763, 88.785065, 11359.494], position);
+
+    let flags = match root.get("flags") {
+        Some(Value::Number(n)) => n,
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(Some(31), flags.as_i64());
+    assert_eq!(Some("0x1F"), flags.as_str());
+    assert_eq!("0x1F", flags.to_string());
+    match root.get("small") {
+        Some(Value::Number(n)) => {
+            assert!(n.is_float());
+            assert_eq!(Some("1e-006"), n.as_str());
+            assert_eq!(0.000001, n.as_f64());
+        }
+        other => panic!("{:?}", other),
+    }
+
+    // Written back, the coordinates read as the same values.
+    let text = serde_arma::to_string(&value).unwrap();
+    assert!(text.starts_with("position[] = {5818.1763, 88.785065, 11359.494};"));
+    assert_eq!(value, serde_arma::from_str::<Value>(&text).unwrap());
+
+    // Numbers read from a config are written as they were, in any format.
+    assert!(text.ends_with("flags = 0x1F;\nsmall = 1e-006;\n"));
+    let config = serde_arma::PrettyConfig::bohemia();
+    let text = serde_arma::to_string_pretty(&value, config).unwrap();
+    assert_eq!(
+        "position[]={5818.1763,88.785065,11359.494};\nflags=0x1F;\nsmall=1e-006;\n",
+        text
+    );
+    let mut root = Class::new();
+    root.insert("precise", Value::from(0.123456789012));
+    let parsed: Number = "0.123456789012".parse().unwrap();
+    root.insert("parsed", Value::Number(parsed));
+    let config = serde_arma::PrettyConfig::bohemia();
+    let text = serde_arma::to_string_pretty(&Value::Class(root), config).unwrap();
+    assert_eq!("precise=0.12345679;\nparsed=0.123456789012;\n", text);
+
+    // Other formats get the text as written.
+    assert_eq!(
+        r#"{"position":["5818.1763","88.785065","11359.494"],"flags":"0x1F","small":"1e-006"}"#,
+        serde_json::to_string(&value).unwrap()
+    );
+    assert_eq!(Ok(Number::from(16)), "0x10".parse::<Number>());
+}